@@ -30,7 +30,7 @@ fn main() -> Result<()> {
         process::exit(65)
     }
 
-    let mut parser = Parser::new(&scanner.tokens());
+    let mut parser = Parser::new(scanner.tokens());
     let stmts = parser.parse_stmt();
 
     if parser.had_error() {
@@ -39,7 +39,7 @@ fn main() -> Result<()> {
 
     let stmts = stmts?;
 
-    let printer = AstPrinter::default();
+    let printer = AstPrinter;
     debug!(
         "Parsed expressions: {:#?}",
         stmts
@@ -55,7 +55,7 @@ fn main() -> Result<()> {
 
     let mut interpreter = Rc::new(RefCell::from(Interpreter::default()));
 
-    let mut resolver = Resolver::new(&interpreter);
+    let mut resolver = Resolver::new();
     if resolver.resolve(&stmts)? {
         process::exit(65)
     }
@@ -14,11 +14,22 @@ pub fn config() -> &'static Config {
 
 #[allow(non_snake_case)]
 #[derive(Debug)]
-pub struct Config {}
+pub struct Config {
+    /// Exit code for scan, parse and resolve errors
+    pub EXIT_CODE_COMPILE_ERROR: i32,
+    /// Exit code for runtime errors
+    pub EXIT_CODE_RUNTIME_ERROR: i32,
+    /// Prints offending source line with a caret after scan errors
+    pub SHOW_SOURCE: bool,
+}
 
 impl Config {
     fn load_from_env() -> Result<Config> {
-        Ok(Config {})
+        Ok(Config {
+            EXIT_CODE_COMPILE_ERROR: get_env_parse_or("LOX_EXIT_CODE_COMPILE_ERROR", 65)?,
+            EXIT_CODE_RUNTIME_ERROR: get_env_parse_or("LOX_EXIT_CODE_RUNTIME_ERROR", 70)?,
+            SHOW_SOURCE: get_env_parse_or("LOX_SHOW_SOURCE", false)?,
+        })
     }
 }
 
@@ -31,3 +42,11 @@ fn _get_env_parse<T: FromStr>(name: &'static str) -> Result<T> {
 
     val.parse::<T>().map_err(|_| Error::ConfigWrongFormat(name))
 }
+
+/// Same as `_get_env_parse`, but falls back to `default` when variable is missing
+fn get_env_parse_or<T: FromStr>(name: &'static str, default: T) -> Result<T> {
+    match _get_env_parse(name) {
+        Err(Error::ConfigMissingEnv(_)) => Ok(default),
+        other => other,
+    }
+}
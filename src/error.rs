@@ -12,7 +12,6 @@ pub enum Error {
     ConfigMissingEnv(&'static str),
     ConfigWrongFormat(&'static str),
 
-    UnknownCommand(String),
     ProgramExecutionError(String),
 
     // -- Modules
@@ -22,6 +21,8 @@ pub enum Error {
     InterpreterError(interpreter::Error),
     #[from]
     ResolverError(resolver::Error),
+    /// Resolver found errors and already reported them
+    ResolveFailed,
 
     // -- Externals
     #[from]
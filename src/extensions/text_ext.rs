@@ -19,10 +19,12 @@ pub trait CharExt {
 }
 
 impl CharExt for char {
+    /// Unicode letters and digits, and `_`
     fn is_alpha_numeric(&self) -> bool {
-        self.is_ascii_digit() || self.is_alpha()
+        self.is_alphanumeric() || *self == '_'
     }
+    /// Unicode letters and `_`
     fn is_alpha(&self) -> bool {
-        self.is_ascii_alphabetic() || *self == '_'
+        self.is_alphabetic() || *self == '_'
     }
 }
@@ -1,24 +1,236 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::cell::RefCell;
+use std::rc::Rc;
 
 use super::MutInterpreter;
-use crate::interpreter::Result;
-use crate::{Token, TokenType, Value};
+use crate::interpreter::{Error, Result};
+use crate::{value, Token, TokenType, Value};
 
-pub fn clock(_interpreter: &MutInterpreter, _args: &[Value]) -> Result<Value> {
-    let start = SystemTime::now();
-    let since_the_epoch = start
-        .duration_since(UNIX_EPOCH)
-        .expect("Time went backwards");
+/// Seconds since the interpreter was created, as in reference Lox. Meant for measuring
+/// elapsed time, not for reading wall-clock time
+pub fn clock(interpreter: &MutInterpreter, _args: &[Value]) -> Result<Value> {
+    Ok(interpreter.borrow().elapsed().as_secs_f64().into())
+}
 
-    // Возвращает время в секундах
-    Ok(Value::Number(since_the_epoch.as_secs_f64()))
+/// Same as `clock`, in milliseconds
+pub fn clock_millis(interpreter: &MutInterpreter, _args: &[Value]) -> Result<Value> {
+    Ok((interpreter.borrow().elapsed().as_secs_f64() * 1000.0).into())
 }
 
+/// Same as `a + b`, errors are reported at the line of the call
 pub fn sum(_interpreter: &MutInterpreter, args: &[Value]) -> Result<Value> {
-    let a = &args[0];
-    let b = &args[1];
+    let a = argument("sum", args, 0)?;
+    let b = argument("sum", args, 1)?;
+
+    let plus = Token::new(TokenType::PLUS, "+", None, 0);
+
+    a.calculate(Some(b), plus).map_err(|_| {
+        value::Error::native("sum", "Operation must be done with numbers or strings.").into()
+    })
+}
+
+pub fn abs(_interpreter: &MutInterpreter, args: &[Value]) -> Result<Value> {
+    let x = number("abs", argument("abs", args, 0)?)?;
+
+    Ok(x.abs().into())
+}
+
+/// Smallest of one or more numbers
+pub fn min(_interpreter: &MutInterpreter, args: &[Value]) -> Result<Value> {
+    args.iter()
+        .try_fold(f64::INFINITY, |min, arg| Ok(min.min(number("min", arg)?)))
+        .map(Value::from)
+}
+
+/// Largest of one or more numbers
+pub fn max(_interpreter: &MutInterpreter, args: &[Value]) -> Result<Value> {
+    args.iter()
+        .try_fold(f64::NEG_INFINITY, |max, arg| {
+            Ok(max.max(number("max", arg)?))
+        })
+        .map(Value::from)
+}
+
+pub fn floor(_interpreter: &MutInterpreter, args: &[Value]) -> Result<Value> {
+    let x = number("floor", argument("floor", args, 0)?)?;
 
-    let res = a.calculate(Some(&b), Token::new(TokenType::PLUS, "+", None, 1));
+    Ok(x.floor().into())
+}
+
+pub fn ceil(_interpreter: &MutInterpreter, args: &[Value]) -> Result<Value> {
+    let x = number("ceil", argument("ceil", args, 0)?)?;
+
+    Ok(x.ceil().into())
+}
+
+/// Rounds half away from zero, `round(-2.5)` is `-3`
+pub fn round(_interpreter: &MutInterpreter, args: &[Value]) -> Result<Value> {
+    let x = number("round", argument("round", args, 0)?)?;
+
+    Ok(x.round().into())
+}
+
+/// Square root of a negative number is `nan`, not an error
+pub fn sqrt(_interpreter: &MutInterpreter, args: &[Value]) -> Result<Value> {
+    let x = number("sqrt", argument("sqrt", args, 0)?)?;
+
+    Ok(x.sqrt().into())
+}
+
+pub fn pow(_interpreter: &MutInterpreter, args: &[Value]) -> Result<Value> {
+    let base = number("pow", argument("pow", args, 0)?)?;
+    let exponent = number("pow", argument("pow", args, 1)?)?;
+
+    Ok(base.powf(exponent).into())
+}
+
+/// Integer division, the quotient is truncated toward zero: `idiv(-7, 2)` is `-3`
+pub fn idiv(_interpreter: &MutInterpreter, args: &[Value]) -> Result<Value> {
+    let (a, b) = divisible("idiv", args)?;
+
+    Ok((a / b).trunc().into())
+}
+
+/// Modulo with the sign of the divisor: `mod(-1, 3)` is `2`, unlike `%` in Rust
+pub fn modulo(_interpreter: &MutInterpreter, args: &[Value]) -> Result<Value> {
+    let (a, b) = divisible("mod", args)?;
+
+    Ok((a - b * (a / b).floor()).into())
+}
+
+/// Keys of a map in insertion order
+pub fn keys(_interpreter: &MutInterpreter, args: &[Value]) -> Result<Value> {
+    match argument("keys", args, 0)? {
+        Value::Map(map) => {
+            let keys = map.borrow().keys().cloned().collect::<Vec<_>>();
+
+            Ok(Value::List(Rc::new(RefCell::new(keys))))
+        }
+        _ => Err(value::Error::native("keys", "Argument must be a map.").into()),
+    }
+}
+
+/// Characters of a string as one-char strings
+pub fn chars(_interpreter: &MutInterpreter, args: &[Value]) -> Result<Value> {
+    match argument("chars", args, 0)? {
+        Value::String(s) => {
+            let chars = s.chars().map(|c| Value::String(c.to_string())).collect();
+
+            Ok(Value::List(Rc::new(RefCell::new(chars))))
+        }
+        _ => Err(value::Error::native("chars", "Argument must be a string.").into()),
+    }
+}
+
+/// Calls `f` with elements of a list as arguments: `apply(f, [1, 2])` is `f(1, 2)`
+pub fn apply(interpreter: &MutInterpreter, args: &[Value]) -> Result<Value> {
+    let (Value::Callable(callable), Value::List(list)) =
+        (argument("apply", args, 0)?, argument("apply", args, 1)?)
+    else {
+        return Err(value::Error::native(
+            "apply",
+            "Arguments must be a function and a list.",
+        ))?;
+    };
+
+    // Copied, so the function can change the list while it runs
+    let arguments = list.borrow().clone();
+
+    // Same error as a direct call with wrong count, line is set to the line of `apply` call
+    let arity = callable.arity();
+    if !arity.accepts(arguments.len()) {
+        let mut token = callable.name().clone();
+        token.line = 0;
+
+        return Err(value::Error::InvalidCountOfArguments {
+            token,
+            count: arguments.len(),
+            expected: arity,
+        }
+        .into());
+    }
+
+    callable.call(interpreter, &arguments)
+}
+
+/// Number written in a string, e.g. `parse_number("1.5")`
+pub fn parse_number(_interpreter: &MutInterpreter, args: &[Value]) -> Result<Value> {
+    let Value::String(s) = argument("parse_number", args, 0)? else {
+        return Err(value::Error::native(
+            "parse_number",
+            "Argument must be a string.",
+        ))?;
+    };
+
+    match s.trim().parse::<f64>() {
+        Ok(n) => Ok(n.into()),
+        Err(_) => Err(value::Error::native(
+            "parse_number",
+            format!("Can't parse '{}' as a number.", s),
+        ))?,
+    }
+}
+
+/// Prints value without trailing newline
+pub fn write(interpreter: &MutInterpreter, args: &[Value]) -> Result<Value> {
+    let interpreter = interpreter.borrow();
+    interpreter.write(&interpreter.stringify(argument("write", args, 0)?));
+
+    Ok(Value::Nil)
+}
+
+/// Fails with runtime error carrying `message` if `condition` is falsey
+pub fn assert(interpreter: &MutInterpreter, args: &[Value]) -> Result<Value> {
+    if interpreter.borrow().is_truthy(argument("assert", args, 0)?) {
+        return Ok(Value::Nil);
+    }
+
+    Err(Error::AssertionFailed {
+        token: Token::new(TokenType::IDENTIFIER, "assert", None, 0),
+        message: argument("assert", args, 1)?.stringify(),
+    })
+}
+
+/// Sorted names of every global variable and native
+pub fn globals(interpreter: &MutInterpreter, _args: &[Value]) -> Result<Value> {
+    let names = interpreter
+        .borrow()
+        .global_names()
+        .into_iter()
+        .map(Value::from)
+        .collect();
+
+    Ok(Value::List(Rc::new(RefCell::new(names))))
+}
+
+/// Argument at `index`. Calls are checked against arity before natives run, this
+/// only keeps natives from panicking when called directly with fewer arguments
+fn argument<'a>(name: &str, args: &'a [Value], index: usize) -> Result<&'a Value> {
+    args.get(index).ok_or_else(|| {
+        value::Error::native(name, format!("Missing argument {}.", index + 1)).into()
+    })
+}
+
+/// Two number arguments, the second one not zero
+fn divisible(name: &str, args: &[Value]) -> Result<(f64, f64)> {
+    let a = number(name, argument(name, args, 0)?)?;
+    let b = number(name, argument(name, args, 1)?)?;
+
+    if b == 0.0 {
+        return Err(value::Error::native(name, "Cannot divide by zero.").into());
+    }
+
+    Ok((a, b))
+}
 
-    Ok(res?)
+/// Number argument of native `name`. Line 0 of the error token is set to the line of the
+/// call by `Value::call`
+fn number(name: &str, value: &Value) -> Result<f64> {
+    match value {
+        Value::Number(n) => Ok(*n),
+        _ => Err(value::Error::must_be_number(
+            Token::new(TokenType::IDENTIFIER, name, None, 0),
+            "Arguments must be numbers.",
+        )
+        .into()),
+    }
 }
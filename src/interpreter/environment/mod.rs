@@ -8,12 +8,36 @@ use crate::{Token, Value};
 
 pub type MutEnv = Rc<RefCell<Environment>>;
 
-#[derive(Debug, Clone, Default, PartialEq)]
+#[derive(Clone, Default)]
 pub struct Environment {
-    values: HashMap<String, Option<Value>>,
+    values: HashMap<Rc<str>, Option<Value>>,
     enclosing: Option<MutEnv>,
 }
 
+/// Enclosing environments are compared by identity. Closures stored in `values` can
+/// point back to this environment, so comparing them by content would never end
+impl PartialEq for Environment {
+    fn eq(&self, other: &Self) -> bool {
+        let same_enclosing = match (&self.enclosing, &other.enclosing) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+
+        same_enclosing && self.values == other.values
+    }
+}
+
+/// Shows only the current frame, enclosing environment is shown by address
+impl core::fmt::Debug for Environment {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        fmt.debug_struct("Environment")
+            .field("values", &self.values)
+            .field("enclosing", &self.enclosing.as_ref().map(Rc::as_ptr))
+            .finish()
+    }
+}
+
 impl Environment {
     pub fn new(enclosing: Option<MutEnv>) -> Self {
         Environment {
@@ -22,52 +46,79 @@ impl Environment {
         }
     }
 
-    pub fn assign_at(&mut self, distance: usize, name: &Token, value: Option<Value>) -> Result<()> {
-        if let Some(ancestor) = self.ancestor(distance) {
-            ancestor.borrow_mut().assign(name, value)?;
+    pub fn assign_at(
+        env: &MutEnv,
+        distance: usize,
+        name: &Token,
+        value: Option<Value>,
+    ) -> Result<()> {
+        if let Some(ancestor) = Self::ancestor(env, distance) {
+            ancestor.borrow_mut().assign(name, value)
+        } else {
+            Err(Error::AncestorNotFound(distance, name.clone()))
         }
+    }
 
-        Ok(())
+    pub fn get_at(env: &MutEnv, distance: usize, name: &Token) -> Result<Value> {
+        if let Some(ancestor) = Self::ancestor(env, distance) {
+            ancestor.borrow().get(name)
+        } else {
+            Err(Error::AncestorNotFound(distance, name.clone()))
+        }
     }
 
-    pub fn get_at(&self, distance: usize, name: &Token) -> Result<Value> {
-        if let Some(ancestor) = self.ancestor(distance) {
-            ancestor.borrow().get(&name)
+    /// Same as `get_at`, but fails for declared variables that were never assigned
+    pub fn get_initialized_at(env: &MutEnv, distance: usize, name: &Token) -> Result<Value> {
+        if let Some(ancestor) = Self::ancestor(env, distance) {
+            ancestor.borrow().get_initialized(name)
         } else {
             Err(Error::AncestorNotFound(distance, name.clone()))
         }
     }
 
-    fn ancestor(&self, distance: usize) -> Option<Rc<RefCell<Environment>>> {
-        let mut env = Rc::new(RefCell::new(self.clone()));
+    /// Environment `distance` hops up the chain. Works on the shared `Rc`,
+    /// so changes made through it are visible to every holder
+    fn ancestor(env: &MutEnv, distance: usize) -> Option<MutEnv> {
+        let mut env = env.clone();
 
         for _ in 0..distance {
-            if let Some(enclosing) = &env.clone().borrow().enclosing {
-                env = Rc::clone(enclosing);
-            }
+            let enclosing = env.borrow().enclosing.clone()?;
+            env = enclosing;
         }
 
         Some(env)
     }
 
+    /// Declared but uninitialized variables read as `nil`
     pub fn get(&self, name: &Token) -> Result<Value> {
+        Ok(self.binding(name)?.unwrap_or(Value::Nil))
+    }
+
+    /// Same as `get`, but fails for declared variables that were never assigned
+    pub fn get_initialized(&self, name: &Token) -> Result<Value> {
+        self.binding(name)?
+            .ok_or_else(|| Error::UninitializedVariable(name.clone()))
+    }
+
+    fn binding(&self, name: &Token) -> Result<Option<Value>> {
         if let Some(value) = self.values.get(&name.lexeme) {
-            return if let Some(value) = value {
-                Ok(value.clone())
-            } else {
-                Ok(Value::Nil)
-            };
+            return Ok(value.clone());
         }
 
         if let Some(enclosing) = &self.enclosing {
-            return enclosing.borrow().get(name);
+            return enclosing.borrow().binding(name);
         }
 
         Err(Error::UndefinedVariable(name.to_owned()))
     }
 
-    pub fn define(&mut self, name: &str, value: Option<Value>) {
-        self.values.insert(name.to_string(), value);
+    /// Names defined in this environment only, enclosing ones are not included
+    pub fn names(&self) -> impl Iterator<Item = &Rc<str>> {
+        self.values.keys()
+    }
+
+    pub fn define(&mut self, name: impl Into<Rc<str>>, value: Option<Value>) {
+        self.values.insert(name.into(), value);
     }
 
     pub fn assign(&mut self, name: &Token, value: Option<Value>) -> Result<()> {
@@ -84,6 +135,52 @@ impl Environment {
 
         Err(Error::UndefinedVariable(name.clone()))
     }
+
+    /// Removes a binding from the current frame. Returns `true` if it existed
+    pub fn undefine(&mut self, name: &str) -> bool {
+        self.values.remove(name).is_some()
+    }
+
+    /// Bindings of the current frame, sorted by name. Uninitialized values are `nil`
+    pub fn variables(&self) -> Vec<(String, Value)> {
+        let mut variables = self
+            .values
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone().unwrap_or(Value::Nil)))
+            .collect::<Vec<_>>();
+
+        variables.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        variables
+    }
+
+    /// Effective binding of every name visible from this frame, enclosing frames included.
+    /// Inner bindings shadow outer ones, uninitialized values are `nil`
+    pub fn visible_bindings(&self) -> HashMap<String, Value> {
+        let mut bindings = match &self.enclosing {
+            Some(enclosing) => enclosing.borrow().visible_bindings(),
+            None => HashMap::new(),
+        };
+
+        for (name, value) in &self.values {
+            bindings.insert(name.to_string(), value.clone().unwrap_or(Value::Nil));
+        }
+
+        bindings
+    }
+
+    /// Count of enclosing scopes. Globals have depth `0`
+    pub fn depth(&self) -> usize {
+        let mut depth = 0;
+        let mut enclosing = self.enclosing.clone();
+
+        while let Some(env) = enclosing {
+            depth += 1;
+            enclosing = env.borrow().enclosing.clone();
+        }
+
+        depth
+    }
 }
 
 // region:    --- Tests
@@ -113,13 +210,33 @@ mod tests {
 
         let token = Token::new(TokenType::IDENTIFIER, "a", None, 1);
 
-        env.define(&token.lexeme, None);
+        env.define(token.lexeme.clone(), None);
 
         assert_eq!(env.get(&token), Ok(Value::Nil));
 
         Ok(())
     }
 
+    #[test]
+    fn test_variable_unitialized_strict_err() -> Result<()> {
+        let mut env = Environment::default();
+
+        let token = Token::new(TokenType::IDENTIFIER, "a", None, 1);
+
+        env.define(token.lexeme.clone(), None);
+
+        assert_eq!(
+            env.get_initialized(&token),
+            Err(Error::UninitializedVariable(token.clone()))
+        );
+
+        env.assign(&token, Some(Value::Nil))?;
+
+        assert_eq!(env.get_initialized(&token), Ok(Value::Nil));
+
+        Ok(())
+    }
+
     #[test]
     fn test_variable_initialized_ok() -> Result<()> {
         let mut env = Environment::default();
@@ -127,7 +244,7 @@ mod tests {
         let token = Token::new(TokenType::IDENTIFIER, "a", None, 1);
         let value = Value::Number(5.5);
 
-        env.define(&token.lexeme, Some(value.clone()));
+        env.define(token.lexeme.clone(), Some(value.clone()));
 
         assert_eq!(env.get(&token), Ok(value));
 
@@ -141,16 +258,147 @@ mod tests {
         let token = Token::new(TokenType::IDENTIFIER, "a", None, 1);
         let value = Value::Number(5.5);
 
-        env.define(&token.lexeme, Some(value.clone()));
+        env.define(token.lexeme.clone(), Some(value.clone()));
 
         assert_eq!(env.get(&token), Ok(value));
 
-        env.define(&token.lexeme, Some(Value::Number(6.6)));
+        env.define(token.lexeme.clone(), Some(Value::Number(6.6)));
 
         assert_eq!(env.get(&token), Ok(Value::Number(6.6)));
 
         Ok(())
     }
+
+    #[test]
+    fn test_variables_nested_scopes_ok() -> Result<()> {
+        let globals = Rc::new(RefCell::new(Environment::default()));
+        globals.borrow_mut().define("a", Some(Value::Number(1.0)));
+
+        let outer = Rc::new(RefCell::new(Environment::new(Some(globals.clone()))));
+        outer.borrow_mut().define("c", None);
+        outer.borrow_mut().define("b", Some(Value::Boolean(true)));
+
+        let inner = Environment::new(Some(outer.clone()));
+
+        assert_eq!(
+            globals.borrow().variables(),
+            vec![("a".to_string(), Value::Number(1.0))]
+        );
+        assert_eq!(
+            outer.borrow().variables(),
+            vec![
+                ("b".to_string(), Value::Boolean(true)),
+                ("c".to_string(), Value::Nil)
+            ]
+        );
+        assert!(inner.variables().is_empty());
+
+        assert_eq!(globals.borrow().depth(), 0);
+        assert_eq!(outer.borrow().depth(), 1);
+        assert_eq!(inner.depth(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_visible_bindings_shadowing_ok() -> Result<()> {
+        let globals = Rc::new(RefCell::new(Environment::default()));
+        globals.borrow_mut().define("a", Some(Value::Number(1.0)));
+        globals.borrow_mut().define("b", Some(Value::Number(2.0)));
+
+        let outer = Rc::new(RefCell::new(Environment::new(Some(globals.clone()))));
+        outer.borrow_mut().define("a", Some(Value::Number(10.0)));
+        outer.borrow_mut().define("c", None);
+
+        let mut inner = Environment::new(Some(outer.clone()));
+        inner.define("a", Some(Value::Number(100.0)));
+
+        let visible = inner.visible_bindings();
+
+        assert_eq!(visible.len(), 3);
+        assert_eq!(visible.get("a"), Some(&Value::Number(100.0)));
+        assert_eq!(visible.get("b"), Some(&Value::Number(2.0)));
+        assert_eq!(visible.get("c"), Some(&Value::Nil));
+        assert_eq!(
+            outer.borrow().visible_bindings().get("a"),
+            Some(&Value::Number(10.0))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_variable_undefine_ok() -> Result<()> {
+        let mut env = Environment::default();
+
+        let token = Token::new(TokenType::IDENTIFIER, "a", None, 1);
+
+        env.define(token.lexeme.clone(), Some(Value::Number(5.5)));
+
+        assert!(env.undefine(&token.lexeme));
+        assert!(!env.undefine(&token.lexeme));
+        assert_eq!(env.get(&token), Err(Error::UndefinedVariable(token)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_assign_at_shared_env_ok() -> Result<()> {
+        let globals = Rc::new(RefCell::new(Environment::default()));
+        globals.borrow_mut().define("a", Some(Value::Number(1.0)));
+
+        let inner = Rc::new(RefCell::new(Environment::new(Some(globals.clone()))));
+        inner.borrow_mut().define("b", Some(Value::Number(2.0)));
+
+        let a = Token::new(TokenType::IDENTIFIER, "a", None, 1);
+        let b = Token::new(TokenType::IDENTIFIER, "b", None, 1);
+
+        Environment::assign_at(&inner, 0, &b, Some(Value::Number(3.0)))?;
+        Environment::assign_at(&inner, 1, &a, Some(Value::Number(4.0)))?;
+
+        assert_eq!(inner.borrow().get(&b), Ok(Value::Number(3.0)));
+        assert_eq!(globals.borrow().get(&a), Ok(Value::Number(4.0)));
+        assert_eq!(Environment::get_at(&inner, 1, &a), Ok(Value::Number(4.0)));
+        assert_eq!(
+            Environment::assign_at(&inner, 2, &a, None),
+            Err(Error::AncestorNotFound(2, a))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_self_referential_debug_eq_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let globals = Rc::new(RefCell::new(Environment::default()));
+        let env = Rc::new(RefCell::new(Environment::new(Some(globals.clone()))));
+
+        // Function stored in the environment it closes over
+        let function = Value::Callable(crate::Callable::Function {
+            name: Box::new(Token::new(TokenType::IDENTIFIER, "f", None, 1)),
+            params: Rc::from([]),
+            body: Rc::from([]),
+            closure: env.clone(),
+        });
+        env.borrow_mut().define("f", Some(function.clone()));
+
+        let other = Rc::new(RefCell::new(Environment::new(Some(globals.clone()))));
+        other.borrow_mut().define("f", Some(function));
+
+        // -- Exec
+        let debug = format!("{:?}", env.borrow());
+
+        // -- Check
+        assert!(debug.contains("Environment"));
+        assert!(*env.borrow() == *env.borrow());
+        assert!(*env.borrow() == *other.borrow());
+
+        other.borrow_mut().define("g", None);
+        assert!(*env.borrow() != *other.borrow());
+        assert!(Environment::new(Some(globals)) != Environment::new(Some(env.clone())));
+
+        Ok(())
+    }
 }
 
 // endregion: --- Tests
@@ -1,11 +1,14 @@
 use derive_more::derive::From;
 
-use crate::{value, Value};
+use std::rc::Rc;
+
+use crate::{value, Token, Value};
 
 use super::environment::{self};
 
 pub type Result<T> = core::result::Result<T, Error>;
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, From)]
 pub enum Error {
     #[from]
@@ -14,6 +17,110 @@ pub enum Error {
     EnvironmentError(environment::Error),
     MutexError(String),
     Return(Value),
+    /// Keyword and target loop label, if any
+    Break(Token, Option<Rc<str>>),
+    Continue(Token, Option<Rc<str>>),
+    /// Program ran past the deadline set with `Interpreter::set_time_limit`
+    TimeLimitExceeded,
+    /// Raised by `assert` native with stringified message
+    AssertionFailed {
+        token: Token,
+        message: String,
+    },
+}
+
+impl Error {
+    /// Line of the offending token, if the error carries one
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Error::ValueError(error) => match error {
+                value::Error::InvalidOperation { token, .. }
+                | value::Error::InvalidType { token, .. }
+                | value::Error::ZeroDivision { token, .. }
+                | value::Error::MustBeNumber { token, .. }
+                | value::Error::MustBeNumberOrString { token, .. }
+                | value::Error::Native { token, .. }
+                | value::Error::NotCallable { token }
+                | value::Error::InvalidCountOfArguments { token, .. } => Some(token.line),
+                value::Error::InvalidConversion { .. } => None,
+            },
+            Error::EnvironmentError(error) => match error {
+                environment::Error::UndefinedVariable(name)
+                | environment::Error::UninitializedVariable(name)
+                | environment::Error::AncestorNotFound(_, name) => Some(name.line),
+            },
+            Error::MutexError(_) | Error::Return(_) | Error::TimeLimitExceeded => None,
+            Error::Break(token, _)
+            | Error::Continue(token, _)
+            | Error::AssertionFailed { token, .. } => Some(token.line),
+        }
+    }
+
+    /// Errors of natives are raised with a line 0 token, which is set to `line` of the call
+    pub(crate) fn with_call_line(mut self, line: usize) -> Self {
+        let token = match &mut self {
+            Error::ValueError(value::Error::Native { token, .. })
+            | Error::ValueError(value::Error::MustBeNumber { token, .. })
+            | Error::ValueError(value::Error::InvalidCountOfArguments { token, .. })
+            | Error::AssertionFailed { token, .. } => token,
+            _ => return self,
+        };
+
+        if token.line == 0 {
+            token.line = line;
+        }
+
+        self
+    }
+
+    /// Message as shown to the user, without the line prefix
+    pub fn user_message(&self) -> String {
+        match self {
+            Error::ValueError(error) => match error {
+                value::Error::InvalidOperation { message, .. }
+                | value::Error::InvalidType { message, .. }
+                | value::Error::ZeroDivision { message, .. }
+                | value::Error::MustBeNumber { message, .. }
+                | value::Error::MustBeNumberOrString { message, .. }
+                | value::Error::Native { message, .. } => message.clone(),
+                value::Error::NotCallable { token } => {
+                    format!("{} is not callable.", token.lexeme)
+                }
+                value::Error::InvalidCountOfArguments {
+                    token,
+                    count,
+                    expected,
+                } => format!(
+                    "{} expected {} arguments but got {}.",
+                    token.lexeme, expected, count
+                ),
+                value::Error::InvalidConversion { expected, found } => {
+                    format!("Expected {} but got {}.", expected, found)
+                }
+            },
+            Error::EnvironmentError(error) => match error {
+                environment::Error::UndefinedVariable(name) => {
+                    format!("Undefined variable '{}'.", name.lexeme)
+                }
+                environment::Error::UninitializedVariable(name) => {
+                    format!("Uninitialized variable '{}'.", name.lexeme)
+                }
+                environment::Error::AncestorNotFound(depth, name) => format!(
+                    "Ancestor with {} not found at depth {}.",
+                    name.lexeme, depth
+                ),
+            },
+            Error::MutexError(message) => message.clone(),
+            Error::Return(_) => String::from("Can't return from top-level code."),
+            Error::Break(_, Some(label)) | Error::Continue(_, Some(label)) => {
+                format!("Undefined loop label '{}'.", label)
+            }
+            Error::Break(_, None) => String::from("Can't use 'break' outside of a loop."),
+            Error::Continue(_, None) => String::from("Can't use 'continue' outside of a loop."),
+            Error::TimeLimitExceeded => String::from("Time limit exceeded."),
+            Error::AssertionFailed { message, .. } => format!("Assertion failed: {}", message),
+        }
+    }
 }
 
 // region:    --- Error Boilerplate
@@ -27,3 +134,102 @@ impl core::fmt::Display for Error {
 impl std::error::Error for Error {}
 
 // endregion: --- Error Boilerplate
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    type Error = Box<dyn std::error::Error>;
+    type Result<T> = core::result::Result<T, Error>; // For tests.
+
+    use crate::value::{self, Arity};
+    use crate::{Interpreter, Parser, Scanner, Token, TokenType};
+
+    #[test]
+    fn test_zero_division_line_and_message_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut scanner = Scanner::from_source("var a = 1;\nprint a / 0;");
+        scanner.scan_tokens()?;
+        let stmts = Parser::new(scanner.tokens()).parse_stmt()?;
+
+        // -- Exec
+        let error = Interpreter::default()
+            .interpret_stmt(&stmts)
+            .expect_err("division by zero must fail");
+
+        // -- Check
+        assert!(matches!(
+            error,
+            super::Error::ValueError(crate::value::Error::ZeroDivision { .. })
+        ));
+        assert_eq!(error.line(), Some(2));
+        assert_eq!(error.user_message(), "Cannot divide by zero.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_value_error_line_and_message_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let token = Token::new(TokenType::IDENTIFIER, "f", None, 7);
+        let errors = [
+            value::Error::invalid_operation(token.clone(), "operation"),
+            value::Error::invalid_type(token.clone(), "type"),
+            value::Error::zero_division(token.clone(), "zero"),
+            value::Error::must_be_number(token.clone(), "number"),
+            value::Error::MustBeNumberOrString {
+                token: token.clone(),
+                message: String::from("number or string"),
+            },
+            value::Error::NotCallable {
+                token: token.clone(),
+            },
+            value::Error::InvalidCountOfArguments {
+                token: token.clone(),
+                count: 1,
+                expected: Arity::Exact(2),
+            },
+            value::Error::Native {
+                token: token.clone(),
+                message: String::from("native"),
+            },
+        ];
+
+        // -- Exec & Check
+        let messages = errors
+            .into_iter()
+            .map(|error| {
+                let error = super::Error::from(error);
+                assert_eq!(error.line(), Some(7));
+
+                error.user_message()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            messages,
+            [
+                "operation",
+                "type",
+                "zero",
+                "number",
+                "number or string",
+                "f is not callable.",
+                "f expected 2 arguments but got 1.",
+                "native",
+            ]
+        );
+
+        // Conversion errors have no position
+        let conversion = super::Error::from(value::Error::InvalidConversion {
+            expected: "number",
+            found: "string",
+        });
+        assert_eq!(conversion.line(), None);
+        assert_eq!(conversion.user_message(), "Expected number but got string.");
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
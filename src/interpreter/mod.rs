@@ -1,7 +1,12 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    io::Write,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
 use crate::{
-    value::{self, CallableFn},
+    value::{Arity, CallableFn, FunctionStyle, NumberEquality, NumberMode, ZeroDivision},
     visitor::{Acceptor, Visitor},
     Callable, Expr, Stmt, Token, TokenType, Value, W,
 };
@@ -17,12 +22,82 @@ use tracing::info;
 
 pub type MutInterpreter = Rc<RefCell<Interpreter>>;
 
+/// Callback invoked before each statement executes, with the statement and its line
+pub type StepHookFn = dyn FnMut(&Stmt, Option<usize>);
+
+#[derive(Clone)]
+pub struct StepHook(Rc<RefCell<StepHookFn>>);
+
+impl StepHook {
+    pub fn call(&self, stmt: &Stmt) {
+        (self.0.borrow_mut())(stmt, stmt.line());
+    }
+}
+
+impl core::fmt::Debug for StepHook {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        write!(fmt, "StepHook")
+    }
+}
+
+/// Copy of global variables taken by [`Interpreter::snapshot`]
+#[derive(Debug, Clone)]
+pub struct Snapshot(Environment);
+
+/// Sink receiving everything the program prints
+pub type OutputFn = dyn FnMut(&str);
+
+#[derive(Clone)]
+pub struct Output(Rc<RefCell<OutputFn>>);
+
+impl Output {
+    pub fn write(&self, text: &str) {
+        (self.0.borrow_mut())(text);
+    }
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Self(Rc::new(RefCell::new(|text: &str| {
+            print!("{}", text);
+            let _ = std::io::stdout().flush();
+        })))
+    }
+}
+
+impl core::fmt::Debug for Output {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        write!(fmt, "Output")
+    }
+}
+
+/// Statements executed between clock reads of the time limit
+const DEADLINE_CHECK_INTERVAL: u32 = 1000;
+
 #[derive(Debug, Default, Clone)]
 pub struct Interpreter {
     had_runtime_error: bool,
+    loose_truthiness: bool,
+    strict_initialization: bool,
+    /// Undefined variables read as `nil`
+    lenient_undefined: bool,
+    function_style: FunctionStyle,
+    pub(crate) number_mode: NumberMode,
+    /// Significant digits of printed numbers, full precision if `None`
+    number_precision: Option<usize>,
+    /// Top-level expression statements print their value, like in a REPL
+    echo_expression_results: bool,
+    /// Moment `clock` counts from, set on creation
+    started: Option<Instant>,
+    /// Wall-clock moment after which statements fail with `TimeLimitExceeded`
+    deadline: Option<Instant>,
+    /// Statements left until `check_deadline` reads the clock again. Shared, because the
+    /// interpreter is cloned for every executed statement
+    steps_until_deadline_check: Rc<Cell<u32>>,
+    pub(crate) step_hook: Option<StepHook>,
+    output: Output,
     pub environment: MutEnv,
     pub globals: MutEnv,
-    pub locals: HashMap<String, usize>,
 }
 
 impl Visitor<Result<Value>> for &MutInterpreter {
@@ -54,12 +129,14 @@ impl From<W<Interpreter>> for MutInterpreter {
 // endregion: --- Froms
 
 impl Interpreter {
+    #[allow(clippy::should_implement_trait)]
     pub fn default() -> Self {
         let globals = Rc::new(RefCell::new(Environment::default()));
 
         let mut interpreter = Self {
             globals: globals.clone(),
             environment: globals,
+            started: Some(Instant::now()),
             ..Default::default()
         };
 
@@ -68,35 +145,207 @@ impl Interpreter {
         interpreter
     }
 
-    pub fn look_up_variable(&self, name: &Token) -> Result<Value> {
-        let value = if let Some(distance) = self.locals.get(&name.lexeme).cloned() {
-            self.environment.borrow().get_at(distance, &name)?
+    /// Makes `0` and `""` falsey in conditions, see [`Value::is_truthy_loose`].
+    /// Off by default
+    pub fn set_loose_truthiness(&mut self, loose: bool) {
+        self.loose_truthiness = loose;
+    }
+
+    pub fn is_truthy(&self, value: &Value) -> bool {
+        if self.loose_truthiness {
+            value.is_truthy_loose()
         } else {
-            self.globals.borrow().get(&name)?
+            value.is_truthy()
+        }
+    }
+
+    /// Makes reading a declared but never assigned variable a runtime error.
+    /// Off by default, such variables read as `nil`
+    pub fn set_strict_initialization(&mut self, strict: bool) {
+        self.strict_initialization = strict;
+    }
+
+    /// Makes reading an undefined variable give `nil` instead of a runtime error.
+    /// Assigning to an undefined variable is still an error. Off by default
+    pub fn set_lenient_undefined(&mut self, lenient: bool) {
+        self.lenient_undefined = lenient;
+    }
+
+    /// Style of user functions in printed output. Reference `<fn name>` by default
+    pub fn set_function_style(&mut self, style: FunctionStyle) {
+        self.function_style = style;
+    }
+
+    /// How numbers are compared by `==` and `!=`. Exact by default
+    pub fn set_number_equality(&mut self, equality: NumberEquality) {
+        self.number_mode.equality = equality;
+    }
+
+    /// Whether dividing by zero is an error or gives IEEE 754 infinity and `NaN`.
+    /// Error by default
+    pub fn set_zero_division(&mut self, zero_division: ZeroDivision) {
+        self.number_mode.zero_division = zero_division;
+    }
+
+    /// Rounds printed numbers to `precision` significant digits, stored values are kept as is.
+    /// Full precision by default
+    pub fn set_number_precision(&mut self, precision: Option<usize>) {
+        self.number_precision = precision;
+    }
+
+    /// Stringifies value for program output, following interpreter settings
+    pub fn stringify(&self, value: &Value) -> String {
+        value.stringify_formatted(self.function_style, self.number_precision)
+    }
+
+    /// Prints value of every top-level expression statement, nested ones are not echoed.
+    /// Off by default
+    pub fn set_echo_expression_results(&mut self, echo: bool) {
+        self.echo_expression_results = echo;
+    }
+
+    /// Time since the interpreter was created
+    pub fn elapsed(&self) -> Duration {
+        self.started
+            .map(|started| started.elapsed())
+            .unwrap_or_default()
+    }
+
+    /// Limits wall-clock time of the program, counted from this call. No limit by default
+    pub fn set_time_limit(&mut self, limit: Option<Duration>) {
+        self.deadline = limit.map(|limit| Instant::now() + limit);
+        self.steps_until_deadline_check.set(0);
+    }
+
+    /// Called before every statement, so loops and recursion can't run past the deadline.
+    /// Clock is read only every `DEADLINE_CHECK_INTERVAL` statements
+    pub(crate) fn check_deadline(&self) -> Result<()> {
+        let Some(deadline) = self.deadline else {
+            return Ok(());
         };
 
-        Ok(value)
+        let steps = self.steps_until_deadline_check.get();
+
+        if steps > 0 {
+            self.steps_until_deadline_check.set(steps - 1);
+            return Ok(());
+        }
+        self.steps_until_deadline_check.set(DEADLINE_CHECK_INTERVAL);
+
+        if Instant::now() >= deadline {
+            return Err(Error::TimeLimitExceeded);
+        }
+
+        Ok(())
+    }
+
+    /// Registers callback invoked before every statement, including nested ones
+    pub fn set_step_hook(&mut self, hook: impl FnMut(&Stmt, Option<usize>) + 'static) {
+        self.step_hook = Some(StepHook(Rc::new(RefCell::new(hook))));
+    }
+
+    pub fn clear_step_hook(&mut self) {
+        self.step_hook = None;
+    }
+
+    /// Redirects program output, stdout by default
+    pub fn set_output(&mut self, output: impl FnMut(&str) + 'static) {
+        self.output = Output(Rc::new(RefCell::new(output)));
+    }
+
+    pub fn write(&self, text: &str) {
+        self.output.write(text);
+    }
+
+    /// Copies global bindings, so [`Interpreter::restore`] can roll them back. Only the
+    /// globals map is copied: lists, maps and environments captured by closures are
+    /// shared with the running program, changes inside them are not rolled back
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot(self.globals.borrow().clone())
+    }
+
+    /// Puts back globals from `snapshot`, variables defined after it are removed
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        *self.globals.borrow_mut() = snapshot.0;
+    }
+
+    /// Names of every global, natives included, sorted
+    pub fn global_names(&self) -> Vec<String> {
+        let mut names = self
+            .globals
+            .borrow()
+            .names()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+
+        names
     }
 
-    pub fn resolve(&mut self, name: &Token, depth: usize) {
-        self.locals.insert(name.lexeme.clone(), depth);
+    /// Reads variable `distance` environments up, or from globals if it was not resolved
+    pub fn look_up_variable(&self, name: &Token, distance: Option<usize>) -> Result<Value> {
+        let value = match (distance, self.strict_initialization) {
+            (Some(distance), false) => Environment::get_at(&self.environment, distance, name),
+            (Some(distance), true) => {
+                Environment::get_initialized_at(&self.environment, distance, name)
+            }
+            (None, false) => self.globals.borrow().get(name),
+            (None, true) => self.globals.borrow().get_initialized(name),
+        };
+
+        match value {
+            Err(environment::Error::UndefinedVariable(_)) if self.lenient_undefined => {
+                Ok(Value::Nil)
+            }
+            value => Ok(value?),
+        }
     }
 
     fn define_natives(&mut self) {
         self.define_native("clock", 0, builtins::clock);
+        self.define_native("clock_millis", 0, builtins::clock_millis);
         self.define_native("sum", 2, builtins::sum);
+        self.define_native("abs", 1, builtins::abs);
+        self.define_native("min", Arity::AtLeast(1), builtins::min);
+        self.define_native("max", Arity::AtLeast(1), builtins::max);
+        self.define_native("floor", 1, builtins::floor);
+        self.define_native("ceil", 1, builtins::ceil);
+        self.define_native("round", 1, builtins::round);
+        self.define_native("sqrt", 1, builtins::sqrt);
+        self.define_native("pow", 2, builtins::pow);
+        self.define_native("idiv", 2, builtins::idiv);
+        self.define_native("mod", 2, builtins::modulo);
+        self.define_native("keys", 1, builtins::keys);
+        self.define_native("chars", 1, builtins::chars);
+        self.define_native("parse_number", 1, builtins::parse_number);
+        self.define_native("apply", 2, builtins::apply);
+        self.define_native("write", 1, builtins::write);
+        self.define_native("assert", 2, builtins::assert);
+        self.define_native("globals", 0, builtins::globals);
+    }
+
+    /// Defines global visible to programs, e.g. host provided constant.
+    /// Redefines it if it already exists
+    pub fn define_global(&mut self, name: &str, value: Value) {
+        self.globals.borrow_mut().define(name, Some(value));
     }
 
-    fn define_native(&mut self, name: impl Into<String>, arity: usize, func: CallableFn) {
-        let name: String = name.into();
+    /// Defines native function callable from programs
+    pub fn define_native(
+        &mut self,
+        name: impl Into<String>,
+        arity: impl Into<Arity>,
+        func: CallableFn,
+    ) {
+        let name = Token::new(TokenType::IDENTIFIER, name.into(), None, 0);
 
         let value = Value::Callable(Callable::BuiltIn {
-            arity,
-            name: Box::new(Token::new(TokenType::IDENTIFIER, &name, None, 0)),
+            arity: arity.into(),
+            name: Box::new(name.clone()),
             function: func,
         });
 
-        self.globals.borrow_mut().define(&name, Some(value));
+        self.globals.borrow_mut().define(name.lexeme, Some(value));
     }
 
     pub fn execute_block(&mut self, stmts: &[Stmt], env: MutEnv) -> Result<()> {
@@ -119,6 +368,22 @@ impl Interpreter {
         Ok(())
     }
 
+    /// Executes `stmts` and evaluates `value` in `env`, like [`Interpreter::execute_block`]
+    pub fn evaluate_block(&mut self, stmts: &[Stmt], value: &Expr, env: MutEnv) -> Result<Value> {
+        let prev = self.environment.clone();
+
+        self.environment = env;
+
+        let result = stmts
+            .iter()
+            .try_for_each(|stmt| self.execute(stmt.clone()))
+            .and_then(|_| value.accept(&W(self.clone()).into()));
+
+        self.environment = prev;
+
+        result
+    }
+
     fn execute(&self, stmt: impl Into<Stmt>) -> Result<()> {
         let stmt: Stmt = stmt.into();
 
@@ -127,6 +392,7 @@ impl Interpreter {
 
     pub fn interpret_expr(&mut self, expr: Expr) -> Result<Value> {
         info!("Interpreting expression...");
+        self.had_runtime_error = false;
         let value = expr.accept(&W(self.clone()).into());
 
         match value {
@@ -139,11 +405,20 @@ impl Interpreter {
         }
     }
 
+    /// Statements run on a clone of the interpreter. Environments, output and hooks are
+    /// shared with the clone through `Rc`, so globals persist between calls
     pub fn interpret_stmt(&mut self, stmts: &[Stmt]) -> Result<()> {
         info!("Interpreting statement...");
+        self.had_runtime_error = false;
 
         for stmt in stmts {
-            let evaluated = stmt.accept(&W(self.clone()).into());
+            let evaluated = match stmt {
+                // Echoed the same way `print` shows values
+                Stmt::Expression(expr) if self.echo_expression_results => {
+                    Stmt::Print(expr.clone()).accept(&W(self.clone()).into())
+                }
+                _ => stmt.accept(&W(self.clone()).into()),
+            };
 
             match evaluated {
                 Ok(_) => {}
@@ -160,53 +435,26 @@ impl Interpreter {
         Ok(())
     }
 
+    /// Resolves `stmts` and interprets them only if resolution succeeded
+    pub fn run_statements(&mut self, stmts: &[Stmt]) -> crate::Result<()> {
+        if crate::Resolver::new().resolve(stmts)? {
+            return Err(crate::Error::ResolveFailed);
+        }
+
+        self.interpret_stmt(stmts)?;
+
+        Ok(())
+    }
+
+    /// Whether the last `interpret_*` call failed, so an interpreter can be reused
     pub fn had_runtime_error(&self) -> bool {
         self.had_runtime_error
     }
 
     fn error(error: &Error) {
-        match error {
-            Error::ValueError(error) => match error {
-                value::Error::InvalidOperation { token, message } => {
-                    crate::report(token.line, message)
-                }
-                value::Error::InvalidType { token, message } => crate::report(token.line, message),
-                value::Error::ZeroDivision { token, message } => crate::report(token.line, message),
-                value::Error::MustBeNumber { token, message } => crate::report(token.line, message),
-                value::Error::MustBeNumberOrString { token, message } => {
-                    crate::report(token.line, message)
-                }
-                value::Error::NotCallable { token } => {
-                    crate::report(token.line, format!("{} is not callable.", token.lexeme));
-                }
-                value::Error::InvalidCountOfArguments {
-                    token,
-                    count,
-                    expected,
-                } => {
-                    crate::report(
-                        token.line,
-                        format!(
-                            "{} expected {} arguments but got {}.",
-                            token.lexeme, expected, count
-                        ),
-                    );
-                }
-            },
-            Error::EnvironmentError(error) => match error {
-                environment::Error::UndefinedVariable(name) => {
-                    crate::report(name.line, format!("Undefined variable '{}'.", name.lexeme))
-                }
-                environment::Error::AncestorNotFound(depth, name) => crate::report(
-                    name.line,
-                    format!(
-                        "Ancestor with {} not found at depth {}.",
-                        name.lexeme, depth
-                    ),
-                ),
-            },
-            Error::MutexError(message) => unreachable!("{}", message),
-            Error::Return(_) => unreachable!(),
+        match error.line() {
+            Some(line) => crate::report(line, error.user_message()),
+            None => eprintln!("Error: {}", error.user_message()),
         }
     }
 }
@@ -307,6 +555,1580 @@ mod tests {
 
         Ok(())
     }
+
+    fn call_native(name: &str, args: &[Value]) -> interpreter::Result<Value> {
+        let expr = Expr::Call {
+            callee: Box::new(Expr::variable(Token::new(
+                TokenType::IDENTIFIER,
+                name,
+                None,
+                1,
+            ))),
+            paren: Token::new(TokenType::RIGHT_PAREN, ")", None, 1),
+            arguments: args
+                .iter()
+                .map(|arg| Expr::Literal(Some(arg.clone())))
+                .collect(),
+        };
+
+        let mut interpreter = interpreter::Interpreter::default();
+        interpreter.interpret_expr(expr)
+    }
+
+    fn parse(source: &str) -> Result<Vec<Stmt>> {
+        let mut scanner = crate::Scanner::from_source(source);
+        scanner.scan_tokens()?;
+
+        Ok(crate::Parser::new(scanner.tokens()).parse_stmt()?)
+    }
+
+    fn run(source: &str) -> Result<interpreter::Interpreter> {
+        let mut interpreter = interpreter::Interpreter::default();
+        interpreter.interpret_stmt(&parse(source)?)?;
+
+        Ok(interpreter)
+    }
+
+    fn run_resolved(source: &str) -> Result<MutInterpreter> {
+        let stmts = parse(source)?;
+
+        let interpreter = Rc::new(RefCell::new(interpreter::Interpreter::default()));
+
+        let had_error = crate::Resolver::new().resolve(&stmts)?;
+        assert!(!had_error, "resolver failed");
+
+        interpreter.borrow_mut().interpret_stmt(&stmts)?;
+
+        Ok(interpreter)
+    }
+
+    fn global(interpreter: &interpreter::Interpreter, name: &str) -> Result<Value> {
+        let token = Token::new(TokenType::IDENTIFIER, name, None, 1);
+
+        Ok(interpreter.globals.borrow().get(&token)?)
+    }
+
+    #[test]
+    fn test_map_insert_read_overwrite_ok() -> Result<()> {
+        let interpreter = run(r#"
+            var m = { "a": 1, "b": 2 };
+            var a = m["a"];
+            m["c"] = 3;
+            var c = m["c"];
+            m["a"] = "one";
+            var overwritten = m["a"];
+            var k = keys(m);
+            "#)?;
+
+        assert_eq!(global(&interpreter, "a")?, Value::Number(1.0));
+        assert_eq!(global(&interpreter, "c")?, Value::Number(3.0));
+        assert_eq!(
+            global(&interpreter, "overwritten")?,
+            Value::String("one".to_string())
+        );
+
+        let Value::List(keys) = global(&interpreter, "k")? else {
+            panic!("keys must return a list");
+        };
+        assert_eq!(
+            *keys.borrow(),
+            vec![
+                Value::String("a".to_string()),
+                Value::String("b".to_string()),
+                Value::String("c".to_string()),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_property_chain_ok() -> Result<()> {
+        let interpreter = run_resolved(
+            r#"
+            fun twice(x) { return x * 2; }
+            var a = { "b": { "c": 1, "twice": twice } };
+            var read = a.b.c;
+            a.b.c = a.b.c + 1;
+            var written = a["b"]["c"];
+            a.b.d = a.b;
+            var called = a.b.d.twice(a.b.c);
+            "#,
+        )?;
+
+        let interpreter = interpreter.borrow();
+        assert_eq!(global(&interpreter, "read")?, Value::Number(1.0));
+        assert_eq!(global(&interpreter, "written")?, Value::Number(2.0));
+        assert_eq!(global(&interpreter, "called")?, Value::Number(4.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_property_err() -> Result<()> {
+        assert!(run(r#"var m = {}; var a = m.missing;"#).is_err());
+
+        for source in [r#"var n = 1; var a = n.b;"#, r#"var s = "s"; s.b = 1;"#] {
+            let error = interpreter::Interpreter::default()
+                .interpret_stmt(&parse(source)?)
+                .expect_err("only maps have properties");
+
+            assert_eq!(error.user_message(), "Only maps have properties.");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_insertion_order_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let printed = Rc::new(RefCell::new(String::new()));
+        let sink = printed.clone();
+
+        let mut interpreter = interpreter::Interpreter::default();
+        interpreter.set_output(move |text| sink.borrow_mut().push_str(text));
+
+        // -- Exec
+        interpreter.interpret_stmt(&parse(
+            r#"
+            var m = { "z": 1, "a": 2 };
+            m["m"] = 3;
+            m["z"] = 4;
+            print m;
+            "#,
+        )?)?;
+
+        // -- Check
+        assert_eq!(*printed.borrow(), "{z: 4, a: 2, m: 3}\n");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_global_redefinition_across_runs_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        // Each line is resolved and run on its own, the same way a REPL feeds input
+        let fx_lines = [
+            "var x = 1;",
+            "var x = x + 1;",
+            "print x;",
+            "var x = \"two\";",
+        ];
+
+        let printed = Rc::new(RefCell::new(String::new()));
+        let sink = printed.clone();
+
+        let interpreter = Rc::new(RefCell::new(interpreter::Interpreter::default()));
+        interpreter
+            .borrow_mut()
+            .set_output(move |text| sink.borrow_mut().push_str(text));
+
+        // -- Exec
+        for line in fx_lines {
+            let stmts = parse(line)?;
+
+            let had_error = crate::Resolver::new().resolve(&stmts)?;
+            assert!(!had_error, "resolver failed on `{}`", line);
+
+            interpreter.borrow_mut().interpret_stmt(&stmts)?;
+        }
+
+        // -- Check
+        assert_eq!(*printed.borrow(), "2\n");
+        assert_eq!(
+            global(&interpreter.borrow(), "x")?,
+            Value::String("two".into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_missing_key_nil_ok() -> Result<()> {
+        let interpreter = run(r#"var m = {}; var missing = m["nope"];"#)?;
+
+        assert_eq!(global(&interpreter, "missing")?, Value::Nil);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_number_bool_keys_ok() -> Result<()> {
+        let interpreter = run(r#"
+            var m = { 1: "one", true: "yes", nil: "none" };
+            m[2] = "two";
+            m[false] = "no";
+            var one = m[1];
+            var two = m[1 + 1];
+            var yes = m[1 == 1];
+            var no = m[false];
+            var none = m[nil];
+            var string_one = m["1"];
+            var k = keys(m);
+            "#)?;
+
+        assert_eq!(global(&interpreter, "one")?, Value::String("one".into()));
+        assert_eq!(global(&interpreter, "two")?, Value::String("two".into()));
+        assert_eq!(global(&interpreter, "yes")?, Value::String("yes".into()));
+        assert_eq!(global(&interpreter, "no")?, Value::String("no".into()));
+        assert_eq!(global(&interpreter, "none")?, Value::String("none".into()));
+        assert_eq!(global(&interpreter, "string_one")?, Value::Nil);
+
+        let Value::List(keys) = global(&interpreter, "k")? else {
+            panic!("keys must return a list");
+        };
+        assert_eq!(
+            *keys.borrow(),
+            vec![
+                Value::Number(1.0),
+                Value::Boolean(true),
+                Value::Nil,
+                Value::Number(2.0),
+                Value::Boolean(false),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_unhashable_key_err() -> Result<()> {
+        assert!(run(r#"var m = {}; m[[1]] = 2;"#).is_err());
+        assert!(run(r#"var m = {}; var a = m[{}];"#).is_err());
+        assert!(run(r#"var m = {}; var a = m[clock];"#).is_err());
+        assert!(run(r#"var m = { [1]: 2 };"#).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_loop_body_error_stops_loop_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let stmts = parse(
+            r#"
+            var count = 0;
+            for (var i = 0; i < 10; i = i + 1) {
+                var inner = i;
+                count = count + 1;
+                if (inner == 2) nil + 1;
+            }
+            "#,
+        )?;
+        assert!(!crate::Resolver::new().resolve(&stmts)?);
+        let mut interpreter = interpreter::Interpreter::default();
+
+        // -- Exec
+        let error = interpreter
+            .interpret_stmt(&stmts)
+            .expect_err("third iteration must fail");
+
+        // -- Check
+        assert!(matches!(error, interpreter::Error::ValueError(_)));
+        assert_eq!(global(&interpreter, "count")?, Value::Number(3.0));
+
+        // Loop and body scopes are left, nothing of them leaked into globals
+        assert!(Rc::ptr_eq(&interpreter.environment, &interpreter.globals));
+        assert!(global(&interpreter, "i").is_err());
+        assert!(global(&interpreter, "inner").is_err());
+
+        interpreter.interpret_stmt(&parse("count = count + 1;")?)?;
+        assert_eq!(global(&interpreter, "count")?, Value::Number(4.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiple_var_declaration_ok() -> Result<()> {
+        let interpreter = run_resolved(
+            r#"
+            var a = 1, b = a + 1, c;
+            var local;
+            { var x = 10, y = x * 2; local = y; }
+            var total = 0;
+            for (var i = 0, j = 10; i < 3; i = i + 1) total = total + i + j;
+            "#,
+        )?;
+
+        assert_eq!(global(&interpreter.borrow(), "a")?, Value::Number(1.0));
+        assert_eq!(global(&interpreter.borrow(), "b")?, Value::Number(2.0));
+        assert_eq!(global(&interpreter.borrow(), "c")?, Value::Nil);
+        assert_eq!(global(&interpreter.borrow(), "local")?, Value::Number(20.0));
+        assert_eq!(global(&interpreter.borrow(), "total")?, Value::Number(33.0));
+
+        assert!(run("var a = 1,;").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_index_ok() -> Result<()> {
+        let interpreter = run(r#"
+            var s = "héllo";
+            var first = s[0];
+            var accented = s[1];
+            var last = s[4];
+            var c = chars("abc");
+            "#)?;
+
+        assert_eq!(global(&interpreter, "first")?, Value::String("h".into()));
+        assert_eq!(global(&interpreter, "accented")?, Value::String("é".into()));
+        assert_eq!(global(&interpreter, "last")?, Value::String("o".into()));
+
+        let Value::List(c) = global(&interpreter, "c")? else {
+            panic!("chars must return a list");
+        };
+        assert_eq!(c.borrow().len(), 3);
+        assert_eq!(c.borrow()[2], Value::String("c".into()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_index_out_of_range_err() -> Result<()> {
+        assert!(run(r#"var a = "abc"[3];"#).is_err());
+        assert!(run(r#"var a = "abc"[-1];"#).is_err());
+        assert!(run(r#"var a = "abc"["0"];"#).is_err());
+        assert!(run(r#"var a = chars(1);"#).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_truthiness_modes_ok() -> Result<()> {
+        let source = r#"
+            var zero = "truthy";
+            if (0) zero = "truthy"; else zero = "falsey";
+            var empty = !"";
+            var either = 0 or "fallback";
+        "#;
+
+        let interpreter = run(source)?;
+
+        assert_eq!(
+            global(&interpreter, "zero")?,
+            Value::String("truthy".into())
+        );
+        assert_eq!(global(&interpreter, "empty")?, Value::Boolean(false));
+        assert_eq!(global(&interpreter, "either")?, Value::Number(0.0));
+
+        let stmts = parse(source)?;
+
+        let mut interpreter = interpreter::Interpreter::default();
+        interpreter.set_loose_truthiness(true);
+        interpreter.interpret_stmt(&stmts)?;
+
+        assert_eq!(
+            global(&interpreter, "zero")?,
+            Value::String("falsey".into())
+        );
+        assert_eq!(global(&interpreter, "empty")?, Value::Boolean(true));
+        assert_eq!(
+            global(&interpreter, "either")?,
+            Value::String("fallback".into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_uninitialized_variable_modes_ok() -> Result<()> {
+        let source = "var a; var b = a;";
+
+        let interpreter = run(source)?;
+        assert_eq!(global(&interpreter, "b")?, Value::Nil);
+
+        let stmts = parse(source)?;
+
+        let mut interpreter = interpreter::Interpreter::default();
+        interpreter.set_strict_initialization(true);
+
+        assert!(matches!(
+            interpreter.interpret_stmt(&stmts),
+            Err(interpreter::Error::EnvironmentError(
+                environment::Error::UninitializedVariable(_)
+            ))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_undefined_variable_modes_ok() -> Result<()> {
+        let source = "var b = missing; var c = b == nil;";
+
+        assert!(matches!(
+            interpreter::Interpreter::default().interpret_stmt(&parse(source)?),
+            Err(interpreter::Error::EnvironmentError(
+                environment::Error::UndefinedVariable(_)
+            ))
+        ));
+
+        let mut interpreter = interpreter::Interpreter::default();
+        interpreter.set_lenient_undefined(true);
+        interpreter.interpret_stmt(&parse(source)?)?;
+
+        assert_eq!(global(&interpreter, "b")?, Value::Nil);
+        assert_eq!(global(&interpreter, "c")?, Value::Boolean(true));
+
+        // Assignment doesn't create variables
+        assert!(interpreter.interpret_stmt(&parse("missing = 1;")?).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_coalesce_nil_ok() -> Result<()> {
+        let interpreter = run(r#"
+            var a = nil ?? 5;
+            var b = false ?? 5;
+            var c = nil ?? nil ?? "last";
+            "#)?;
+
+        assert_eq!(global(&interpreter, "a")?, Value::Number(5.0));
+        assert_eq!(global(&interpreter, "b")?, Value::Boolean(false));
+        assert_eq!(global(&interpreter, "c")?, Value::String("last".into()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_coalesce_short_circuit_ok() -> Result<()> {
+        let interpreter = run(r#"
+            var called = false;
+            fun sideEffect() { called = true; return 1; }
+            var a = 3 ?? sideEffect();
+            "#)?;
+
+        assert_eq!(global(&interpreter, "a")?, Value::Number(3.0));
+        assert_eq!(global(&interpreter, "called")?, Value::Boolean(false));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_time_limit_infinite_loop_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut interpreter = interpreter::Interpreter::default();
+        interpreter.set_time_limit(Some(Duration::from_millis(20)));
+
+        // -- Exec
+        let result = interpreter.interpret_stmt(&parse("var i = 0; while (true) { i = i + 1; }")?);
+
+        // -- Check
+        assert!(matches!(result, Err(interpreter::Error::TimeLimitExceeded)));
+        assert!(interpreter.had_runtime_error());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_time_limit_checked_every_interval_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut interpreter = interpreter::Interpreter::default();
+        interpreter.set_time_limit(Some(Duration::from_secs(60)));
+
+        // -- Exec & Check
+        interpreter.check_deadline()?;
+        interpreter.deadline = Some(Instant::now());
+
+        // Statements run on clones of the interpreter, which share the count
+        let clone = interpreter.clone();
+        for _ in 0..DEADLINE_CHECK_INTERVAL {
+            clone.check_deadline()?;
+        }
+        assert!(matches!(
+            interpreter.check_deadline(),
+            Err(interpreter::Error::TimeLimitExceeded)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_step_hook_records_lines_ok() -> Result<()> {
+        let stmts = parse("var a = 1;\n{\n  var b = a;\n}\nfun f() {\n  return a;\n}\nf();")?;
+
+        let lines = Rc::new(RefCell::new(Vec::new()));
+        let recorded = lines.clone();
+
+        let mut interpreter = interpreter::Interpreter::default();
+        interpreter.set_step_hook(move |_, line| recorded.borrow_mut().push(line));
+        interpreter.interpret_stmt(&stmts)?;
+
+        assert_eq!(
+            *lines.borrow(),
+            vec![Some(1), Some(3), Some(3), Some(5), Some(8), Some(6)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_for_continue_runs_increment_ok() -> Result<()> {
+        let interpreter = run_resolved(
+            r#"
+            var out = "";
+            var iterations = 0;
+            for (var i = 0; i < 3; i = i + 1) {
+                iterations = iterations + 1;
+                if (iterations > 10) break;
+                if (i == 1) continue;
+                out = out + i;
+            }
+            "#,
+        )?;
+
+        let interpreter = interpreter.borrow();
+        assert_eq!(global(&interpreter, "out")?, Value::String("02".into()));
+        assert_eq!(global(&interpreter, "iterations")?, Value::Number(3.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_while_break_ok() -> Result<()> {
+        let interpreter = run_resolved(
+            r#"
+            var i = 0;
+            while (true) {
+                if (i == 5) break;
+                i = i + 1;
+            }
+            "#,
+        )?;
+
+        assert_eq!(global(&interpreter.borrow(), "i")?, Value::Number(5.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_labeled_break_continue_ok() -> Result<()> {
+        let interpreter = run_resolved(
+            r#"
+            var broken = "";
+            outer: while (true) {
+                while (true) {
+                    broken = broken + "a";
+                    break outer;
+                }
+                broken = broken + "b";
+            }
+
+            var continued = "";
+            var i = 0;
+            var j = 0;
+            outer: while (i < 3) {
+                i = i + 1;
+                j = 0;
+                while (j < 3) {
+                    j = j + 1;
+                    if (j == 2) continue outer;
+                    continued = continued + i + j;
+                }
+                continued = continued + "x";
+            }
+            "#,
+        )?;
+
+        let interpreter = interpreter.borrow();
+        assert_eq!(global(&interpreter, "broken")?, Value::String("a".into()));
+        assert_eq!(
+            global(&interpreter, "continued")?,
+            Value::String("112131".into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_for_closures_share_loop_variable_ok() -> Result<()> {
+        let interpreter = run_resolved(
+            r#"
+            var first;
+            var second;
+            for (var i = 0; i < 2; i = i + 1) {
+                fun show() { return i; }
+                if (i == 0) first = show; else second = show;
+            }
+            var a = first();
+            var b = second();
+            "#,
+        )?;
+
+        // Both closures see `i` after the last increment
+        let interpreter = interpreter.borrow();
+        assert_eq!(global(&interpreter, "a")?, Value::Number(2.0));
+        assert_eq!(global(&interpreter, "b")?, Value::Number(2.0));
+        // Loop variable doesn't leak into the enclosing scope
+        assert!(global(&interpreter, "i").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolved_scope_per_use_ok() -> Result<()> {
+        // `a` inside `show` is resolved to the global before the block declares its own
+        let interpreter = run_resolved(
+            r#"
+            var a = "global";
+            var first;
+            var second;
+            var local;
+            {
+                fun show() { return a; }
+                first = show();
+                var a = "block";
+                second = show();
+                {
+                    local = a;
+                }
+            }
+            "#,
+        )?;
+
+        let interpreter = interpreter.borrow();
+        assert_eq!(
+            global(&interpreter, "first")?,
+            Value::String("global".into())
+        );
+        assert_eq!(
+            global(&interpreter, "second")?,
+            Value::String("global".into())
+        );
+        assert_eq!(
+            global(&interpreter, "local")?,
+            Value::String("block".into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_statements_resolves_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut interpreter = interpreter::Interpreter::default();
+
+        // -- Exec
+        interpreter.run_statements(&parse(
+            r#"
+            var a = "global";
+            var first;
+            var second;
+            {
+                fun show() { return a; }
+                first = show();
+                var a = "block";
+                second = show();
+                print a;
+            }
+            "#,
+        )?)?;
+
+        // -- Check
+        assert_eq!(global(&interpreter, "first")?, Value::from("global"));
+        assert_eq!(global(&interpreter, "second")?, Value::from("global"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_statements_resolve_error_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut interpreter = interpreter::Interpreter::default();
+
+        // -- Exec
+        let result = interpreter.run_statements(&parse("var reached = true; return 1;")?);
+
+        // -- Check
+        assert!(matches!(result, Err(crate::Error::ResolveFailed)));
+        assert!(interpreter
+            .globals
+            .borrow()
+            .names()
+            .all(|name| &**name != "reached"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_return_from_nested_block_ok() -> Result<()> {
+        let interpreter = run_resolved(
+            r#"
+            var result;
+            var after;
+            fun f() {
+                var x = "local";
+                {
+                    var y = "nested";
+                    {
+                        return x + y;
+                    }
+                }
+            }
+            {
+                var outer = "outer";
+                result = f();
+                after = outer;
+            }
+            var global_after = result;
+            "#,
+        )?;
+
+        let interpreter = interpreter.borrow();
+        assert_eq!(
+            global(&interpreter, "result")?,
+            Value::String("localnested".into())
+        );
+        assert_eq!(
+            global(&interpreter, "after")?,
+            Value::String("outer".into())
+        );
+        assert_eq!(
+            global(&interpreter, "global_after")?,
+            Value::String("localnested".into())
+        );
+        assert!(Rc::ptr_eq(&interpreter.environment, &interpreter.globals));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_function_body_error_surfaces_err() -> Result<()> {
+        let stmts = parse(
+            r#"
+            var reached = false;
+            fun divide(a) {
+                var before = a;
+                return before / 0;
+            }
+            var result = divide(1);
+            reached = true;
+            "#,
+        )?;
+
+        let interpreter = Rc::new(RefCell::new(interpreter::Interpreter::default()));
+        assert!(!crate::Resolver::new().resolve(&stmts)?);
+
+        let result = interpreter.borrow_mut().interpret_stmt(&stmts);
+        let interpreter = interpreter.borrow();
+
+        let Err(error) = result else {
+            panic!("error in function body must reach the caller");
+        };
+        assert!(matches!(
+            error,
+            interpreter::Error::ValueError(crate::value::Error::ZeroDivision { .. })
+        ));
+        assert_eq!(error.line(), Some(5));
+        assert_eq!(global(&interpreter, "reached")?, Value::Boolean(false));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_operator_error_line_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_cases = [
+            ("var a = 1\n  +\n  nil;", 2),
+            ("var a = 1 /\n\n  0;", 1),
+            ("var a = 1\n  <\n  \"b\";", 2),
+            ("var a =\n  -\n  \"b\";", 2),
+        ];
+
+        for (source, line) in fx_cases {
+            // -- Exec
+            let error = interpreter::Interpreter::default()
+                .interpret_stmt(&parse(source)?)
+                .expect_err("operation must fail");
+
+            // -- Check
+            assert_eq!(error.line(), Some(line), "wrong line for {:?}", source);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unary_plus_ok() -> Result<()> {
+        // -- Exec
+        let interpreter = run("var a = +3; var b = -+a;")?;
+
+        // -- Check
+        assert_eq!(global(&interpreter, "a")?, Value::Number(3.0));
+        assert_eq!(global(&interpreter, "b")?, Value::Number(-3.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unary_plus_err() -> Result<()> {
+        // -- Exec
+        let error = interpreter::Interpreter::default()
+            .interpret_stmt(&parse("print +\"x\";")?)
+            .expect_err("unary plus on a string");
+
+        // -- Check
+        assert_eq!(error.user_message(), "Operand must be a number.");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_chained_comparison_err() -> Result<()> {
+        // -- Exec
+        let error = interpreter::Interpreter::default()
+            .interpret_stmt(&parse("var a = 0;\nprint 1 < 2 < 3;")?)
+            .expect_err("boolean compared with number");
+
+        // -- Check
+        assert_eq!(error.line(), Some(2));
+        assert_eq!(
+            error.user_message(),
+            "Cannot compare boolean with number; did you mean a chained comparison?"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recursive_local_function_ok() -> Result<()> {
+        let interpreter = run_resolved(
+            r#"
+            var in_block;
+            var in_function;
+            {
+                fun fib(n) {
+                    if (n < 2) return n;
+                    return fib(n - 1) + fib(n - 2);
+                }
+                in_block = fib(10);
+            }
+
+            fun outer() {
+                fun countdown(n) {
+                    if (n == 0) return "done";
+                    return countdown(n - 1);
+                }
+                return countdown(5);
+            }
+            in_function = outer();
+            "#,
+        )?;
+
+        let interpreter = interpreter.borrow();
+        assert_eq!(global(&interpreter, "in_block")?, Value::Number(55.0));
+        assert_eq!(global(&interpreter, "in_function")?, Value::from("done"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_call_returned_function_ok() -> Result<()> {
+        let interpreter = run_resolved(
+            r#"
+            fun makeAdder(a) {
+                fun add(b) {
+                    return a + b;
+                }
+                return add;
+            }
+            fun curry(a) {
+                fun second(b) {
+                    fun third(c) {
+                        return a + b + c;
+                    }
+                    return third;
+                }
+                return second;
+            }
+            var seven = makeAdder(3)(4);
+            var six = curry(1)(2)(3);
+            var addTen = makeAdder(10);
+            var eleven = addTen(1);
+            var twelve = addTen(2);
+            "#,
+        )?;
+
+        let interpreter = interpreter.borrow();
+        assert_eq!(global(&interpreter, "seven")?, Value::Number(7.0));
+        assert_eq!(global(&interpreter, "six")?, Value::Number(6.0));
+        assert_eq!(global(&interpreter, "eleven")?, Value::Number(11.0));
+        assert_eq!(global(&interpreter, "twelve")?, Value::Number(12.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_expression_initializer_ok() -> Result<()> {
+        let interpreter = run_resolved(
+            r#"
+            var answer = { var a = 40; a + 2 };
+            var single = { "only" };
+            fun triple(n) {
+                return { var t = 3; n * t };
+            }
+            var nine = triple(3);
+            var outer = 1;
+            var shadowed = { var outer = 2; outer };
+            var map = { "a": 1 };
+            var empty = {};
+            "#,
+        )?;
+
+        let interpreter = interpreter.borrow();
+        assert_eq!(global(&interpreter, "answer")?, Value::Number(42.0));
+        assert_eq!(
+            global(&interpreter, "single")?,
+            Value::String("only".into())
+        );
+        assert_eq!(global(&interpreter, "nine")?, Value::Number(9.0));
+        assert_eq!(global(&interpreter, "shadowed")?, Value::Number(2.0));
+        assert_eq!(global(&interpreter, "outer")?, Value::Number(1.0));
+        assert!(global(&interpreter, "a").is_err());
+        assert!(matches!(global(&interpreter, "map")?, Value::Map(_)));
+        assert!(matches!(global(&interpreter, "empty")?, Value::Map(_)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_native_write_no_newline_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let output = Rc::new(RefCell::new(String::new()));
+        let sink = output.clone();
+
+        let mut interpreter = interpreter::Interpreter::default();
+        interpreter.set_output(move |text| sink.borrow_mut().push_str(text));
+
+        // -- Exec
+        interpreter.interpret_stmt(&parse(
+            r#"
+            write("a");
+            write(1);
+            print "b";
+            write(nil);
+            "#,
+        )?)?;
+
+        // -- Check
+        assert_eq!(output.borrow().as_str(), "a1b\nnil");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clock_elapsed_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut interpreter = interpreter::Interpreter::default();
+
+        // -- Exec
+        interpreter.interpret_stmt(&parse("var a = clock(); var a_ms = clock_millis();")?)?;
+        std::thread::sleep(Duration::from_millis(20));
+        interpreter.interpret_stmt(&parse("var b = clock(); var b_ms = clock_millis();")?)?;
+
+        // -- Check
+        let number = |name| -> Result<f64> { Ok(f64::try_from(global(&interpreter, name)?)?) };
+        let (a, b) = (number("a")?, number("b")?);
+        let (a_ms, b_ms) = (number("a_ms")?, number("b_ms")?);
+
+        // Counted from interpreter creation, not from the epoch
+        assert!((0.0..60.0).contains(&a), "clock started at {}", a);
+        assert!(b - a >= 0.02 && b - a < 60.0, "delta {}", b - a);
+        assert!(b_ms - a_ms >= 20.0 && b_ms > b, "delta {}ms", b_ms - a_ms);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zero_division_mode_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_source = "var inf = 1 / 0; var nan = 0 / 0;";
+
+        // -- Exec & Check
+        let mut interpreter = interpreter::Interpreter::default();
+        assert!(interpreter.interpret_stmt(&parse(fx_source)?).is_err());
+
+        let mut interpreter = interpreter::Interpreter::default();
+        interpreter.set_zero_division(ZeroDivision::Ieee);
+        interpreter.interpret_stmt(&parse(fx_source)?)?;
+
+        assert_eq!(global(&interpreter, "inf")?, Value::Number(f64::INFINITY));
+        assert!(global(&interpreter, "nan")?
+            .as_number()
+            .is_some_and(f64::is_nan));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_number_equality_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_source = "var equal = 0.1 + 0.2 == 0.3;";
+        let fx_cases = [
+            (NumberEquality::Exact, false),
+            (NumberEquality::Tolerant, true),
+        ];
+
+        for (equality, expected) in fx_cases {
+            let mut interpreter = interpreter::Interpreter::default();
+            interpreter.set_number_equality(equality);
+
+            // -- Exec
+            interpreter.interpret_stmt(&parse(fx_source)?)?;
+
+            // -- Check
+            assert_eq!(
+                global(&interpreter, "equal")?,
+                Value::Boolean(expected),
+                "{:?}",
+                equality
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_echo_expression_results_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_source = r#"1 + 2; var a = "x"; a; { 5; } print "end";"#;
+        let fx_cases = [(false, "end\n"), (true, "3\nx\nend\n")];
+
+        for (echo, expected) in fx_cases {
+            let printed = Rc::new(RefCell::new(String::new()));
+            let sink = printed.clone();
+
+            let mut interpreter = interpreter::Interpreter::default();
+            interpreter.set_output(move |text| sink.borrow_mut().push_str(text));
+            interpreter.set_echo_expression_results(echo);
+
+            // -- Exec
+            interpreter.interpret_stmt(&parse(fx_source)?)?;
+
+            // -- Check
+            assert_eq!(*printed.borrow(), expected, "echo {}", echo);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_number_precision_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_source = "var sum = 0.1 + 0.2; print sum; print 1234.5678; print -0.000123456; \
+            var third = 1 / 3; print [third, [2 / 3]]; print {third: 2 / 3};";
+        let fx_cases = [
+            (
+                None,
+                "0.30000000000000004\n1234.5678\n-0.000123456\n\
+                [0.3333333333333333, [0.6666666666666666]]\n\
+                {0.3333333333333333: 0.6666666666666666}\n",
+            ),
+            (
+                Some(2),
+                "0.3\n1200\n-0.00012\n[0.33, [0.67]]\n{0.33: 0.67}\n",
+            ),
+            (
+                Some(6),
+                "0.3\n1234.57\n-0.000123456\n[0.333333, [0.666667]]\n{0.333333: 0.666667}\n",
+            ),
+        ];
+
+        for (precision, expected) in fx_cases {
+            let printed = Rc::new(RefCell::new(String::new()));
+            let sink = printed.clone();
+
+            let mut interpreter = interpreter::Interpreter::default();
+            interpreter.set_output(move |text| sink.borrow_mut().push_str(text));
+            interpreter.set_number_precision(precision);
+
+            // -- Exec
+            interpreter.interpret_stmt(&parse(fx_source)?)?;
+
+            // -- Check
+            assert_eq!(*printed.borrow(), expected, "precision {:?}", precision);
+            assert_eq!(
+                global(&interpreter, "sum")?,
+                Value::Number(0.1 + 0.2),
+                "stored value must not change"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_print_function_style_ok() -> Result<()> {
+        for (style, expected) in [
+            (None, "<fn add>\n<native fn clock>\n"),
+            (
+                Some(FunctionStyle::Reference),
+                "<fn add>\n<native fn clock>\n",
+            ),
+            (
+                Some(FunctionStyle::Verbose),
+                "<fn add(a, b)>\n<native fn clock>\n",
+            ),
+        ] {
+            // -- Setup & Fixtures
+            let output = Rc::new(RefCell::new(String::new()));
+            let sink = output.clone();
+
+            let mut interpreter = interpreter::Interpreter::default();
+            interpreter.set_output(move |text| sink.borrow_mut().push_str(text));
+
+            if let Some(style) = style {
+                interpreter.set_function_style(style);
+            }
+
+            // -- Exec
+            interpreter.interpret_stmt(&parse(
+                "fun add(a, b) { return a + b; }\nprint add;\nprint clock;",
+            )?)?;
+
+            // -- Check
+            assert_eq!(output.borrow().as_str(), expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_native_abs_ok() -> Result<()> {
+        assert_eq!(
+            call_native("abs", &[Value::Number(-3.5)])?,
+            Value::Number(3.5)
+        );
+        assert_eq!(
+            call_native("abs", &[Value::Number(2.0)])?,
+            Value::Number(2.0)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_native_number_argument_err() -> Result<()> {
+        for call in [
+            "abs(\"x\")",
+            "min(\"x\")",
+            "max(\"x\")",
+            "floor(\"x\")",
+            "ceil(\"x\")",
+            "round(\"x\")",
+            "sqrt(\"x\")",
+            "pow(2, \"x\")",
+        ] {
+            // -- Exec
+            let error = interpreter::Interpreter::default()
+                .interpret_stmt(&parse(&format!("print 1;\n\nprint {};", call))?)
+                .expect_err("string is not a number");
+
+            // -- Check
+            assert!(
+                matches!(
+                    error,
+                    interpreter::Error::ValueError(crate::value::Error::MustBeNumber { .. })
+                ),
+                "{}",
+                call
+            );
+            assert_eq!(error.user_message(), "Arguments must be numbers.");
+            assert_eq!(error.line(), Some(3), "{}", call);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_native_min_max_ok() -> Result<()> {
+        let args = [Value::Number(3.0), Value::Number(-1.0)];
+
+        assert_eq!(call_native("min", &args)?, Value::Number(-1.0));
+        assert_eq!(call_native("max", &args)?, Value::Number(3.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_native_variadic_ok() -> Result<()> {
+        let args = [4.0, -2.0, 9.5, 0.0, 3.0].map(Value::Number);
+
+        assert_eq!(call_native("min", &args)?, Value::Number(-2.0));
+        assert_eq!(call_native("max", &args)?, Value::Number(9.5));
+        assert_eq!(call_native("max", &args[..1])?, Value::Number(4.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_native_variadic_below_minimum_err() -> Result<()> {
+        let error = call_native("max", &[]).expect_err("max needs an argument");
+
+        assert!(matches!(
+            &error,
+            interpreter::Error::ValueError(crate::value::Error::InvalidCountOfArguments {
+                count: 0,
+                expected: Arity::AtLeast(1),
+                ..
+            })
+        ));
+        assert!(error
+            .user_message()
+            .contains("expected at least 1 arguments"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_native_rounding_ok() -> Result<()> {
+        let fx_cases = [
+            ("floor", 2.7, 2.0),
+            ("floor", -2.2, -3.0),
+            ("ceil", 2.2, 3.0),
+            ("ceil", -2.7, -2.0),
+            ("round", 2.5, 3.0),
+            ("round", -2.5, -3.0),
+            ("round", 2.4, 2.0),
+        ];
+
+        for (name, x, expected) in fx_cases {
+            assert_eq!(
+                call_native(name, &[Value::Number(x)])?,
+                Value::Number(expected),
+                "{}({})",
+                name,
+                x
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_native_sqrt_pow_ok() -> Result<()> {
+        assert_eq!(
+            call_native("sqrt", &[Value::Number(16.0)])?,
+            Value::Number(4.0)
+        );
+        assert!(matches!(
+            call_native("sqrt", &[Value::Number(-1.0)])?,
+            Value::Number(n) if n.is_nan()
+        ));
+        assert_eq!(
+            call_native("pow", &[Value::Number(2.0), Value::Number(10.0)])?,
+            Value::Number(1024.0)
+        );
+        assert_eq!(
+            call_native("pow", &[Value::Number(4.0), Value::Number(0.5)])?,
+            Value::Number(2.0)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_native_missing_argument_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let interpreter: MutInterpreter = W(interpreter::Interpreter::default()).into();
+
+        // -- Exec
+        // Direct calls skip the arity check of `Expr::Call`
+        let error = builtins::sum(&interpreter, &[Value::Number(1.0)]).expect_err("one argument");
+
+        // -- Check
+        assert_eq!(error.user_message(), "Missing argument 2.");
+        assert!(builtins::pow(&interpreter, &[]).is_err());
+        assert!(builtins::apply(&interpreter, &[]).is_err());
+
+        // Through a call the arity is checked first
+        assert!(matches!(
+            call_native("sum", &[Value::Number(1.0)]),
+            Err(interpreter::Error::ValueError(
+                crate::value::Error::InvalidCountOfArguments { .. }
+            ))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_native_sum_err() -> Result<()> {
+        // -- Exec
+        let error = interpreter::Interpreter::default()
+            .interpret_stmt(&parse("\n\nprint sum(\"a\", nil);")?)
+            .expect_err("string and nil can't be added");
+
+        // -- Check
+        assert_eq!(
+            error.user_message(),
+            "Operation must be done with numbers or strings."
+        );
+        assert_eq!(error.line(), Some(3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_native_idiv_mod_ok() -> Result<()> {
+        let n = Value::Number;
+
+        assert_eq!(call_native("idiv", &[n(7.0), n(2.0)])?, n(3.0));
+        assert_eq!(call_native("idiv", &[n(-7.0), n(2.0)])?, n(-3.0));
+        assert_eq!(call_native("mod", &[n(-1.0), n(3.0)])?, n(2.0));
+        assert_eq!(call_native("mod", &[n(7.0), n(3.0)])?, n(1.0));
+        assert_eq!(call_native("mod", &[n(1.0), n(-3.0)])?, n(-2.0));
+
+        for name in ["idiv", "mod"] {
+            let error = call_native(name, &[n(1.0), n(0.0)]).expect_err("zero divisor");
+            assert_eq!(error.user_message(), "Cannot divide by zero.");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_native_apply_ok() -> Result<()> {
+        let interpreter = run_resolved(
+            r#"
+            fun add(a, b) { return a + b; }
+            var user = apply(add, [1, 2]);
+            var native = apply(max, [3, 9, 4]);
+            var nested = apply(apply, [add, ["a", "b"]]);
+            "#,
+        )?;
+
+        assert_eq!(global(&interpreter.borrow(), "user")?, Value::Number(3.0));
+        assert_eq!(global(&interpreter.borrow(), "native")?, Value::Number(9.0));
+        assert_eq!(global(&interpreter.borrow(), "nested")?, Value::from("ab"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_native_apply_err() -> Result<()> {
+        let error = interpreter::Interpreter::default()
+            .interpret_stmt(&parse("fun add(a, b) { return a + b; }\napply(add, [1]);")?)
+            .expect_err("too few arguments");
+        assert!(matches!(
+            &error,
+            interpreter::Error::ValueError(crate::value::Error::InvalidCountOfArguments {
+                count: 1,
+                expected: crate::value::Arity::Exact(2),
+                ..
+            })
+        ));
+        assert_eq!(error.user_message(), "add expected 2 arguments but got 1.");
+        assert_eq!(error.line(), Some(2));
+
+        assert!(run("apply(abs, [1, 2]);").is_err());
+        assert!(run("apply(max, []);").is_err());
+        assert!(run("apply(1, [1]);").is_err());
+        assert!(run("fun f() {} apply(f, nil);").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_global_names_ok() -> Result<()> {
+        // -- Exec
+        let interpreter = run("var answer = 42; var names = globals();")?;
+
+        // -- Check
+        let names = interpreter.global_names();
+        for native in ["clock", "sum", "keys", "globals", "answer", "names"] {
+            assert!(
+                names.iter().any(|name| name == native),
+                "missing {}",
+                native
+            );
+        }
+
+        let mut sorted = names.clone();
+        sorted.sort();
+        assert_eq!(names, sorted);
+
+        // `names` itself is defined only after `globals()` returns
+        let Value::List(listed) = global(&interpreter, "names")? else {
+            panic!("globals must return a list");
+        };
+        assert!(listed.borrow().contains(&Value::from("answer")));
+        assert!(!listed.borrow().contains(&Value::from("names")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_native_custom_error_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut interpreter = interpreter::Interpreter::default();
+        interpreter.define_native("fail", 1, |_, args| {
+            Err(crate::value::Error::native(
+                "fail",
+                format!("Failed with {}.", args[0].stringify()),
+            ))?
+        });
+
+        // -- Exec
+        let error = interpreter
+            .interpret_stmt(&parse("var a = 1;\n\nfail(a + 1);")?)
+            .expect_err("native must fail");
+        let parsed = run("var n = parse_number(\" 2.5 \");")?;
+
+        // -- Check
+        assert!(interpreter.had_runtime_error());
+        assert_eq!(error.user_message(), "Failed with 2.");
+        assert_eq!(error.line(), Some(3));
+
+        assert_eq!(global(&parsed, "n")?, Value::Number(2.5));
+        assert!(run(r#"var n = parse_number("abc");"#).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reuse_across_calls_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let printed = Rc::new(RefCell::new(String::new()));
+        let sink = printed.clone();
+        let mut interpreter = interpreter::Interpreter::default();
+        interpreter.set_output(move |text| sink.borrow_mut().push_str(text));
+
+        let run = |interpreter: &mut interpreter::Interpreter, source: &str| -> Result<bool> {
+            let stmts = parse(source)?;
+            assert!(!crate::Resolver::new().resolve(&stmts)?);
+
+            Ok(interpreter.interpret_stmt(&stmts).is_ok())
+        };
+
+        // -- Exec & Check
+        assert!(run(
+            &mut interpreter,
+            "var count = 0; fun counter() { var n = 0; fun inc() { n = n + 1; count = count + 1; return n; } return inc; } var inc = counter();",
+        )?);
+        assert!(run(&mut interpreter, "print inc(); print inc();")?);
+
+        assert!(!run(&mut interpreter, "{ var local = inc(); nil + 1; }")?);
+        assert!(interpreter.had_runtime_error());
+        assert!(Rc::ptr_eq(&interpreter.environment, &interpreter.globals));
+
+        assert!(run(&mut interpreter, "print inc();")?);
+        assert!(!interpreter.had_runtime_error());
+
+        assert_eq!(*printed.borrow(), "1\n2\n4\n");
+        assert_eq!(global(&interpreter, "count")?, Value::Number(4.0));
+        assert!(global(&interpreter, "local").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_restore_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut interpreter = run("var kept = 1; var changed = 1;")?;
+        let snapshot = interpreter.snapshot();
+
+        // -- Exec
+        interpreter.interpret_stmt(&parse("var added = 2; changed = 3;")?)?;
+        assert_eq!(global(&interpreter, "added")?, Value::Number(2.0));
+
+        interpreter.restore(snapshot);
+
+        // -- Check
+        assert!(global(&interpreter, "added").is_err());
+        assert_eq!(global(&interpreter, "kept")?, Value::Number(1.0));
+        assert_eq!(global(&interpreter, "changed")?, Value::Number(1.0));
+        assert!(interpreter.interpret_stmt(&parse("print added;")?).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_define_global_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut interpreter = interpreter::Interpreter::default();
+        interpreter.define_global("VERSION", Value::from("1.2.0"));
+        interpreter.define_native("double", 1, |_, args| {
+            Ok(Value::Number(args[0].as_number().unwrap_or_default() * 2.0))
+        });
+
+        // -- Exec
+        interpreter.interpret_stmt(&parse(
+            r#"var version = "v" + VERSION; var doubled = double(21);"#,
+        )?)?;
+
+        // -- Check
+        assert_eq!(global(&interpreter, "version")?, Value::from("v1.2.0"));
+        assert_eq!(global(&interpreter, "doubled")?, Value::Number(42.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_native_assert_ok() -> Result<()> {
+        let interpreter = run(r#"
+            var reached = false;
+            assert(1 + 1 == 2, "math works");
+            reached = true;
+            "#)?;
+
+        assert_eq!(global(&interpreter, "reached")?, Value::Boolean(true));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_native_assert_err() -> Result<()> {
+        let mut interpreter = interpreter::Interpreter::default();
+
+        let result = interpreter.interpret_stmt(&parse(
+            r#"
+            var reached = false;
+            assert(1 + 1 == 3, 1 + 1);
+            reached = true;
+            "#,
+        )?);
+
+        let Err(error) = result else {
+            panic!("failing assert must stop execution");
+        };
+        assert!(
+            matches!(&error, interpreter::Error::AssertionFailed { message, .. } if message == "2")
+        );
+        assert_eq!(error.user_message(), "Assertion failed: 2");
+        assert_eq!(error.line(), Some(3));
+        assert!(interpreter.had_runtime_error());
+        assert_eq!(global(&interpreter, "reached")?, Value::Boolean(false));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_native_math_not_number_err() -> Result<()> {
+        let string = Value::String("hello".to_string());
+
+        for result in [
+            call_native("abs", std::slice::from_ref(&string)),
+            call_native("min", &[Value::Number(1.0), string.clone()]),
+            call_native("max", &[string.clone(), Value::Number(1.0)]),
+            call_native("floor", std::slice::from_ref(&string)),
+            call_native("ceil", std::slice::from_ref(&string)),
+            call_native("round", std::slice::from_ref(&string)),
+            call_native("sqrt", std::slice::from_ref(&string)),
+            call_native("pow", &[Value::Number(2.0), string.clone()]),
+            call_native("pow", &[string.clone(), Value::Number(2.0)]),
+        ] {
+            assert!(matches!(
+                result,
+                Err(interpreter::Error::ValueError(
+                    crate::value::Error::MustBeNumber { message, .. }
+                )) if message == "Arguments must be numbers."
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 // endregion: --- Tests
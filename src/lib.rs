@@ -1,7 +1,5 @@
 // region:    --- Modules
 
-use std::usize;
-
 use tracing::info;
 use tracing_subscriber::EnvFilter;
 
@@ -13,6 +11,7 @@ mod interpreter;
 mod parser;
 mod printer;
 mod resolver;
+pub mod runner;
 mod scanner;
 mod token;
 mod tree;
@@ -22,14 +21,16 @@ mod visitor;
 // -- Flatten
 pub use config::config;
 pub use error::{Error, Result};
-pub use interpreter::{Interpreter, MutInterpreter};
+pub use interpreter::{Interpreter, MutInterpreter, Snapshot};
 pub use parser::Parser;
-pub use printer::AstPrinter;
+pub use printer::{AstPrinter, JsonPrinter, SourcePrinter};
 pub use resolver::Resolver;
-pub use scanner::Scanner;
+pub use scanner::{ScanResult, Scanner};
 pub use token::{Token, TokenType};
-pub use tree::{Expr, Stmt};
-pub use value::{Callable, CallableFn, Value};
+pub use tree::{Expr, Node, Stmt};
+pub use value::{
+    Arity, Callable, CallableFn, FunctionStyle, NumberEquality, NumberMode, Value, ZeroDivision,
+};
 pub use visitor::Visitor;
 
 // endregion: --- Modules
@@ -40,6 +41,27 @@ pub fn report(line: usize, message: impl Into<String>) {
     eprintln!("[line {}] Error: {}", line, message.into());
 }
 
+/// Line `line` of `source` with a caret under `column`, both 1-based
+pub fn snippet(source: &str, line: usize, column: usize) -> String {
+    let text = source
+        .lines()
+        .nth(line.saturating_sub(1))
+        .unwrap_or_default();
+    let gutter = line.to_string();
+
+    format!(
+        "{} | {}\n{} | {:>column$}",
+        gutter,
+        text,
+        " ".repeat(gutter.len()),
+        "^"
+    )
+}
+
+pub fn report_warning(line: usize, message: impl Into<String>) {
+    eprintln!("[line {}] Warning: {}", line, message.into());
+}
+
 pub fn init() -> Result<()> {
     // LOGGING INITIALIZATION
     tracing_subscriber::fmt()
@@ -2,141 +2,99 @@ pub type Result<T> = core::result::Result<T, Error>;
 
 use std::env;
 use std::process;
+use std::time::Duration;
 
-use interpreter::AstPrinter;
+use interpreter::runner::{self, AstFormat, Command, ExitStatus, RunOptions};
 use interpreter::Error;
-use interpreter::Interpreter;
-use interpreter::Parser;
-use interpreter::Scanner;
+
+/// Prints `message` and help to stderr, then exits with usage error code
+fn exit_with_usage(program: &str, message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, runner::usage(program));
+    process::exit(ExitStatus::Usage.code())
+}
 
 fn main() -> Result<()> {
     _ = interpreter::init();
 
     let args: Vec<String> = env::args().collect();
-    if args.len() < 3 {
-        Err(Error::ProgramExecutionError(format!(
-            "Usage: {} tokenize <filename>",
-            args[0]
-        )))?;
-    }
-
-    let command = &args[1];
-    let filename = &args[2];
-
-    match command.as_str() {
-        "tokenize" => {
-            tokenize(filename)?;
-        }
-        "parse" => {
-            parse(filename)?;
-        }
-        "evaluate" => {
-            evaluate(filename)?;
-        }
-        "run" => {
-            run(filename)?;
-        }
-        _ => Err(Error::UnknownCommand(args[0].to_string()))?,
-    }
-
-    Ok(())
-}
 
-fn tokenize(filename: &str) -> Result<()> {
-    let mut scanner = Scanner::new(filename)?;
+    let (flags, args): (Vec<&String>, Vec<&String>) =
+        args.iter().partition(|arg| arg.starts_with("--"));
 
-    scanner.scan_tokens()?;
+    let program = args
+        .first()
+        .map_or("interpreter", |program| program.as_str());
+    let has_flag = |flag: &str| flags.iter().any(|f| *f == flag);
 
-    for token in scanner.tokens() {
-        println!("{}", token);
+    if has_flag("--help") || args.get(1).is_some_and(|command| *command == "help") {
+        println!("{}", runner::usage(program));
+        return Ok(());
     }
 
-    if scanner.had_error() {
-        process::exit(65)
-    }
-
-    Ok(())
-}
-
-fn parse(filename: &str) -> Result<()> {
-    let mut scanner = Scanner::new(filename)?;
-
-    scanner.scan_tokens()?;
-
-    if scanner.had_error() {
-        process::exit(65)
-    }
-
-    let mut parser = Parser::new(&scanner.tokens());
-    let expr = parser.parse_expr();
-
-    match expr {
-        Ok(expr) => {
-            let printer = AstPrinter::default();
-            let result = printer.print(&expr);
-
-            println!("{}", result);
+    let command = match args.get(1) {
+        Some(name) => match Command::from_name(name) {
+            Some(command) => command,
+            None => exit_with_usage(program, &format!("Unknown command '{}'.", name)),
+        },
+        None => exit_with_usage(program, "Missing command."),
+    };
+
+    let Some(filename) = args.get(2) else {
+        exit_with_usage(program, "Missing filename.")
+    };
+
+    // `--time-limit=<seconds>`
+    let time_limit = flags
+        .iter()
+        .find_map(|flag| flag.strip_prefix("--time-limit="))
+        .map(|seconds| {
+            seconds
+                .parse::<f64>()
+                .ok()
+                .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                .unwrap_or_else(|| {
+                    exit_with_usage(program, &format!("Invalid time limit '{}'.", seconds))
+                })
+        });
+
+    let run_options = RunOptions {
+        strict: has_flag("--strict"),
+        time_limit,
+        dump_resolver: has_flag("--dump-resolver"),
+        no_resolve: has_flag("--no-resolve"),
+    };
+
+    let ast_format = if has_flag("--ast-json") {
+        AstFormat::Json
+    } else {
+        AstFormat::Lisp
+    };
+
+    let result = match command {
+        Command::Tokenize => runner::tokenize(filename),
+        Command::Parse => runner::parse_as(filename, ast_format),
+        Command::Dump => runner::dump(filename),
+        Command::Evaluate => runner::evaluate(filename),
+        Command::Run => runner::run_with(filename, run_options),
+        Command::Benchmark => runner::benchmark(filename),
+    };
+
+    // Unreadable source is reported as a plain message, not as a debug-printed error
+    let status = match result {
+        Ok(status) => status,
+        Err(Error::ProgramExecutionError(message)) => {
+            eprintln!("{}", message);
+            ExitStatus::InputError
         }
-        Err(_) => process::exit(65),
-    }
-
-    Ok(())
-}
-
-fn evaluate(filename: &str) -> Result<()> {
-    let mut scanner = Scanner::new(filename)?;
-
-    scanner.scan_tokens()?;
-
-    if scanner.had_error() {
-        process::exit(65)
-    }
-
-    let mut parser = Parser::new(&scanner.tokens());
-    let expr = parser.parse_expr();
-
-    if parser.had_error() {
-        process::exit(65)
-    }
-
-    let mut interpreter = Interpreter::default();
-    let result = interpreter.interpret_expr(expr?);
-
-    if interpreter.had_runtime_error() {
-        process::exit(70)
-    }
-
-    match result {
-        Ok(value) => {
-            println!("{}", value.stringify());
+        Err(Error::IoError(error)) => {
+            eprintln!("Could not read file '{}': {}.", filename, error);
+            ExitStatus::InputError
         }
-        Err(_) => process::exit(70),
-    }
-
-    Ok(())
-}
-
-fn run(filename: &str) -> Result<()> {
-    let mut scanner = Scanner::new(filename)?;
-
-    scanner.scan_tokens()?;
-
-    if scanner.had_error() {
-        process::exit(65)
-    }
-
-    let mut parser = Parser::new(&scanner.tokens());
-    let stmts = parser.parse_stmt();
-
-    if parser.had_error() {
-        process::exit(65)
-    }
-
-    let mut interpreter = Interpreter::default();
-    _ = interpreter.interpret_stmt(&stmts?);
+        Err(error) => return Err(error),
+    };
 
-    if interpreter.had_runtime_error() {
-        process::exit(70)
+    if !status.is_ok() {
+        process::exit(status.code())
     }
 
     Ok(())
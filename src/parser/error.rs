@@ -8,7 +8,46 @@ pub enum Error {
     ExpectExpression(Token),
     UnexpectedToken(Token, String),
     InvalidAssignmentTarget(Token),
-    TooManyArguments(Token),
+    TooManyArguments(Token, ArgumentList),
+    /// Tokens left after a complete expression, first of them
+    TrailingTokens(Token),
+}
+
+/// List that went over the 255 items limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgumentList {
+    Parameters,
+    Arguments,
+}
+
+impl Error {
+    /// Message as shown to the user, without the line prefix
+    pub fn message(&self) -> String {
+        match self {
+            Error::UnknownExpression(_) => String::from("Unknown expression."),
+            Error::UnexpectedToken(_, message) => message.clone(),
+            Error::ExpectExpression(_) => String::from("Expect expression."),
+            Error::InvalidAssignmentTarget(_) => String::from("Invalid assignment target."),
+            Error::TooManyArguments(_, ArgumentList::Parameters) => {
+                String::from("Can't have more than 255 parameters.")
+            }
+            Error::TooManyArguments(_, ArgumentList::Arguments) => {
+                String::from("Can't have more than 255 arguments.")
+            }
+            Error::TrailingTokens(_) => String::from("Unexpected trailing tokens."),
+        }
+    }
+
+    pub fn token(&self) -> &Token {
+        match self {
+            Error::UnknownExpression(token)
+            | Error::ExpectExpression(token)
+            | Error::UnexpectedToken(token, _)
+            | Error::InvalidAssignmentTarget(token)
+            | Error::TooManyArguments(token, _)
+            | Error::TrailingTokens(token) => token,
+        }
+    }
 }
 
 // region:    --- Error Boilerplate
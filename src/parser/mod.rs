@@ -1,22 +1,38 @@
+use std::cell::Cell;
+
 use tracing::info;
 
 use crate::{tree::Expr, Stmt, Token, TokenType, Value};
 
 mod error;
 
-pub use error::{Error, Result};
+pub use error::{ArgumentList, Error, Result};
 
 #[derive(Debug, Default)]
 pub struct Parser {
     current: usize,
     tokens: Vec<Token>,
-    had_error: bool,
+    /// Every reported error, parsing goes on after each of them
+    errors: Vec<Error>,
+    /// Count of statement blocks being parsed, their `}` stops error recovery
+    blocks: usize,
 }
 
 impl Parser {
+    /// Tokens are terminated with EOF if they aren't already
     pub fn new(tokens: &[Token]) -> Parser {
+        Self::from_tokens(tokens.to_vec())
+    }
+
+    /// Same as [`Parser::new`], takes ownership of tokens instead of cloning them
+    pub fn from_tokens(mut tokens: Vec<Token>) -> Parser {
+        if tokens.last().map(|token| &token.token_type) != Some(&TokenType::EOF) {
+            let line = tokens.last().map(|token| token.line).unwrap_or(1);
+            tokens.push(Token::eof(line));
+        }
+
         Parser {
-            tokens: tokens.to_vec(),
+            tokens,
             ..Default::default()
         }
     }
@@ -29,28 +45,26 @@ impl Parser {
         let mut stmts = Vec::new();
 
         while !self.is_end() {
-            let stmt = self.declaration();
-
-            match stmt {
-                Ok(stmt) => stmts.push(stmt),
-                Err(e) => {
-                    self.had_error = true;
-                    Self::error(&e);
-                    return Err(e);
-                }
+            match self.declaration() {
+                Ok(declared) => stmts.extend(declared),
+                Err(e) => self.error(e),
             }
         }
 
-        Ok(stmts)
+        match self.errors.first() {
+            Some(e) => Err(e.clone()),
+            None => Ok(stmts),
+        }
     }
 
-    fn declaration(&mut self) -> Result<Stmt> {
+    /// Several statements only for `var a, b;`, every other declaration is one statement
+    fn declaration(&mut self) -> Result<Vec<Stmt>> {
         let stmt = if self.matches(&[TokenType::FUN]) {
-            self.function("function")
+            self.function("function").map(|stmt| vec![stmt])
         } else if self.matches(&[TokenType::VAR]) {
             self.var_declaration()
         } else {
-            self.statement()
+            self.statement().map(|stmt| vec![stmt])
         };
 
         match stmt {
@@ -65,14 +79,17 @@ impl Parser {
     fn function(&mut self, kind: impl Into<String>) -> Result<Stmt> {
         let name = self.consume(TokenType::IDENTIFIER, "Expect function name.")?;
 
-        self.consume(TokenType::LEFT_PAREN, "Expect '(' after function name.")?;
+        self.consume_after(TokenType::LEFT_PAREN, "Expect '(' after function name.")?;
 
         let mut params = Vec::new();
 
         if !self.check(TokenType::RIGHT_PAREN) {
             loop {
                 if params.len() >= 255 {
-                    return Err(Error::TooManyArguments(self.peek()));
+                    return Err(Error::TooManyArguments(
+                        self.peek(),
+                        ArgumentList::Parameters,
+                    ));
                 }
 
                 params.push(self.consume(TokenType::IDENTIFIER, "Expect parameter name.")?);
@@ -83,7 +100,7 @@ impl Parser {
             }
         }
 
-        self.consume(TokenType::RIGHT_PAREN, "Expect ')' after parameters.")?;
+        self.consume_after(TokenType::RIGHT_PAREN, "Expect ')' after parameters.")?;
 
         self.consume(
             TokenType::LEFT_BRACE,
@@ -95,26 +112,42 @@ impl Parser {
         Ok(Stmt::Function { name, params, body })
     }
 
-    fn var_declaration(&mut self) -> Result<Stmt> {
-        let name = self.consume(TokenType::IDENTIFIER, "Expect variable name.")?;
+    /// `var a = 1, b;` gives a `Stmt::Var` per name, in the same scope and in order,
+    /// so later initializers see earlier variables
+    fn var_declaration(&mut self) -> Result<Vec<Stmt>> {
+        let mut vars = Vec::new();
 
-        let mut initializer = None;
+        loop {
+            let name = self.consume(TokenType::IDENTIFIER, "Expect variable name.")?;
 
-        if self.matches(&[TokenType::EQUAL]) {
-            initializer = Some(Box::new(self.expression()?));
+            let mut initializer = None;
+
+            if self.matches(&[TokenType::EQUAL]) {
+                initializer = Some(Box::new(self.expression()?));
+            }
+
+            vars.push(Stmt::Var { name, initializer });
+
+            if !self.matches(&[TokenType::COMMA]) {
+                break;
+            }
         }
 
-        self.consume(
+        self.consume_after(
             TokenType::SEMICOLON,
             "Expect ';' after variable declaration.",
         )?;
 
-        Ok(Stmt::Var { name, initializer })
+        Ok(vars)
     }
 
     fn statement(&mut self) -> Result<Stmt> {
+        if self.check(TokenType::IDENTIFIER) && self.check_next(TokenType::COLON) {
+            return self.labeled_statement();
+        }
+
         if self.matches(&[TokenType::FOR]) {
-            return self.for_statement();
+            return self.for_statement(None);
         }
 
         if self.matches(&[TokenType::IF]) {
@@ -130,7 +163,21 @@ impl Parser {
         }
 
         if self.matches(&[TokenType::WHILE]) {
-            return self.while_statement();
+            return self.while_statement(None);
+        }
+
+        if self.matches(&[TokenType::BREAK]) {
+            let keyword = self.previous();
+            let label = self.loop_label();
+            self.consume_after(TokenType::SEMICOLON, "Expect ';' after 'break'.")?;
+            return Ok(Stmt::Break { keyword, label });
+        }
+
+        if self.matches(&[TokenType::CONTINUE]) {
+            let keyword = self.previous();
+            let label = self.loop_label();
+            self.consume_after(TokenType::SEMICOLON, "Expect ';' after 'continue'.")?;
+            return Ok(Stmt::Continue { keyword, label });
         }
 
         if self.matches(&[TokenType::LEFT_BRACE]) {
@@ -140,6 +187,34 @@ impl Parser {
         self.expression_statement()
     }
 
+    /// `name: while (...)` or `name: for (...)`
+    fn labeled_statement(&mut self) -> Result<Stmt> {
+        let label = self.advance();
+        self.advance();
+
+        if self.matches(&[TokenType::FOR]) {
+            return self.for_statement(Some(label));
+        }
+
+        if self.matches(&[TokenType::WHILE]) {
+            return self.while_statement(Some(label));
+        }
+
+        Err(Error::UnexpectedToken(
+            self.peek(),
+            String::from("Expect loop after label."),
+        ))
+    }
+
+    /// Optional target of `break` and `continue`
+    fn loop_label(&mut self) -> Option<Token> {
+        if self.matches(&[TokenType::IDENTIFIER]) {
+            return Some(self.previous());
+        }
+
+        None
+    }
+
     fn return_statement(&mut self) -> Result<Stmt> {
         let keyword = self.previous();
         let mut value = None;
@@ -148,20 +223,23 @@ impl Parser {
             value = Some(Box::new(self.expression()?));
         }
 
-        self.consume(TokenType::SEMICOLON, "Expect ';' after return value.")?;
+        self.consume_after(TokenType::SEMICOLON, "Expect ';' after return value.")?;
 
         Ok(Stmt::Return { keyword, value })
     }
 
-    fn for_statement(&mut self) -> Result<Stmt> {
-        self.consume(TokenType::LEFT_PAREN, "Expect '(' after 'for'.")?;
+    /// Desugared into `{ initializer; while (condition) body increment }`. Loop variable
+    /// lives in that block, once for the whole loop, so closures created in different
+    /// iterations share it and see its latest value
+    fn for_statement(&mut self, label: Option<Token>) -> Result<Stmt> {
+        self.consume_after(TokenType::LEFT_PAREN, "Expect '(' after 'for'.")?;
 
         let initializer = if self.matches(&[TokenType::SEMICOLON]) {
             None
         } else if self.matches(&[TokenType::VAR]) {
             Some(self.var_declaration()?)
         } else {
-            Some(self.expression_statement()?)
+            Some(vec![self.expression_statement()?])
         };
 
         let condition = if !self.check(TokenType::SEMICOLON) {
@@ -170,7 +248,7 @@ impl Parser {
             Expr::Literal(Some(Value::Boolean(true)))
         };
 
-        self.consume(TokenType::SEMICOLON, "Expect ';' after loop condition.")?;
+        self.consume_after(TokenType::SEMICOLON, "Expect ';' after loop condition.")?;
 
         let increment = if !self.check(TokenType::RIGHT_PAREN) {
             Some(self.expression()?)
@@ -178,43 +256,44 @@ impl Parser {
             None
         };
 
-        self.consume(TokenType::RIGHT_PAREN, "Expect ')' after for clauses.")?;
-
-        let mut body = self.statement()?;
+        self.consume_after(TokenType::RIGHT_PAREN, "Expect ')' after for clauses.")?;
 
-        if let Some(increment) = increment {
-            body = Stmt::Block(vec![body, Stmt::Expression(Box::new(increment))]);
-        }
+        let body = self.statement()?;
 
-        body = Stmt::While {
+        let mut body = Stmt::While {
             condition: Box::new(condition),
             body: Box::new(body),
+            increment: increment.map(Box::new),
+            label,
         };
 
-        if let Some(initializer) = initializer {
-            body = Stmt::Block(vec![initializer, body]);
+        if let Some(mut initializer) = initializer {
+            initializer.push(body);
+            body = Stmt::Block(initializer);
         }
 
         Ok(body)
     }
 
-    fn while_statement(&mut self) -> Result<Stmt> {
-        self.consume(TokenType::LEFT_PAREN, "Expect '(' after 'while'.")?;
+    fn while_statement(&mut self, label: Option<Token>) -> Result<Stmt> {
+        self.consume_after(TokenType::LEFT_PAREN, "Expect '(' after 'while'.")?;
         let condition = self.expression();
-        self.consume(TokenType::RIGHT_PAREN, "Expect ')' after condition.")?;
+        self.consume_after(TokenType::RIGHT_PAREN, "Expect ')' after condition.")?;
 
         let body = self.statement();
 
         Ok(Stmt::While {
             condition: Box::new(condition?),
             body: Box::new(body?),
+            increment: None,
+            label,
         })
     }
 
     fn if_statement(&mut self) -> Result<Stmt> {
-        self.consume(TokenType::LEFT_PAREN, "Expect '(' after 'if'.")?;
+        self.consume_after(TokenType::LEFT_PAREN, "Expect '(' after 'if'.")?;
         let condition = self.expression();
-        self.consume(TokenType::RIGHT_PAREN, "Expect ')' after condition.")?;
+        self.consume_after(TokenType::RIGHT_PAREN, "Expect ')' after condition.")?;
 
         let then_branch = self.statement();
 
@@ -230,30 +309,39 @@ impl Parser {
         })
     }
 
+    /// Errors of inner statements are reported here, so the rest of the block and
+    /// statements after it are still parsed
     fn block(&mut self) -> Result<Vec<Stmt>> {
         let mut statements = Vec::new();
 
+        self.blocks += 1;
+
         while !self.check(TokenType::RIGHT_BRACE) && !self.is_end() {
-            statements.push(self.declaration()?);
+            match self.declaration() {
+                Ok(declared) => statements.extend(declared),
+                Err(e) => self.error(e),
+            }
         }
 
-        self.consume(TokenType::RIGHT_BRACE, "Expect '}' after block.")?;
+        self.blocks -= 1;
+
+        self.consume_after(TokenType::RIGHT_BRACE, "Expect '}' after block.")?;
 
         Ok(statements)
     }
 
     fn print_statement(&mut self) -> Result<Stmt> {
-        let value = self.expression();
-        self.consume(TokenType::SEMICOLON, "Expect ';' after value.")?;
-        Ok(Stmt::Print(Box::new(value?)))
+        let value = self.expression()?;
+        self.consume_after(TokenType::SEMICOLON, "Expect ';' after value.")?;
+        Ok(Stmt::Print(Box::new(value)))
     }
 
     fn expression_statement(&mut self) -> Result<Stmt> {
-        let expr = self.expression();
+        let expr = self.expression()?;
 
-        self.consume(TokenType::SEMICOLON, "Expect ';' after expression.")?;
+        self.consume_after(TokenType::SEMICOLON, "Expect ';' after expression.")?;
 
-        Ok(Stmt::Expression(Box::new(expr?)))
+        Ok(Stmt::Expression(Box::new(expr)))
     }
 
     // endregion: --- Statements
@@ -262,13 +350,18 @@ impl Parser {
 
     pub fn parse_expr(&mut self) -> Result<Expr> {
         info!("Parsing tokens into Expr...");
-        let result = self.expression();
+        let result = self.expression().and_then(|expr| {
+            if !self.is_end() {
+                return Err(Error::TrailingTokens(self.peek()));
+            }
+
+            Ok(expr)
+        });
 
         match result {
             Ok(expr) => Ok(expr),
             Err(e) => {
-                self.had_error = true;
-                Self::error(&e);
+                self.error(e.clone());
                 Err(e)
             }
         }
@@ -279,17 +372,40 @@ impl Parser {
     }
 
     fn assignment(&mut self) -> Result<Expr> {
-        let expr = self.or();
+        let expr = self.coalesce();
 
         if self.matches(&[TokenType::EQUAL]) {
             let equals = self.previous();
             let value = self.assignment();
 
-            if let Expr::Variable(name) = expr.clone()? {
-                return Ok(Expr::Assign {
-                    name: name.clone(),
-                    value: Box::new(value?),
-                });
+            match expr.clone()? {
+                Expr::Variable { name, .. } => {
+                    return Ok(Expr::Assign {
+                        name,
+                        value: Box::new(value?),
+                        distance: Cell::default(),
+                    });
+                }
+                Expr::Index {
+                    object,
+                    bracket,
+                    index,
+                } => {
+                    return Ok(Expr::SetIndex {
+                        object,
+                        bracket,
+                        index,
+                        value: Box::new(value?),
+                    });
+                }
+                Expr::Get { object, name } => {
+                    return Ok(Expr::Set {
+                        object,
+                        name,
+                        value: Box::new(value?),
+                    });
+                }
+                _ => {}
             }
 
             Err(Error::InvalidAssignmentTarget(equals))?;
@@ -298,6 +414,21 @@ impl Parser {
         expr
     }
 
+    fn coalesce(&mut self) -> Result<Expr> {
+        let mut expr = self.or();
+
+        while self.matches(&[TokenType::QUESTION_QUESTION]) {
+            let right = self.or();
+
+            expr = Ok(Expr::Coalesce {
+                left: Box::new(expr?),
+                right: Box::new(right?),
+            });
+        }
+
+        expr
+    }
+
     fn or(&mut self) -> Result<Expr> {
         let mut expr = self.and();
 
@@ -406,7 +537,7 @@ impl Parser {
     }
 
     fn unary(&mut self) -> Result<Expr> {
-        while self.matches(&[TokenType::BANG, TokenType::MINUS]) {
+        if self.matches(&[TokenType::BANG, TokenType::MINUS, TokenType::PLUS]) {
             let operator = self.previous();
             let right = self.unary();
 
@@ -425,6 +556,24 @@ impl Parser {
         loop {
             if self.matches(&[TokenType::LEFT_PAREN]) {
                 expr = self.finish_call(expr?);
+            } else if self.matches(&[TokenType::LEFT_BRACKET]) {
+                let index = self.expression()?;
+                let bracket =
+                    self.consume_after(TokenType::RIGHT_BRACKET, "Expect ']' after index.")?;
+
+                expr = Ok(Expr::Index {
+                    object: Box::new(expr?),
+                    bracket,
+                    index: Box::new(index),
+                });
+            } else if self.matches(&[TokenType::DOT]) {
+                let name =
+                    self.consume_after(TokenType::IDENTIFIER, "Expect property name after '.'.")?;
+
+                expr = Ok(Expr::Get {
+                    object: Box::new(expr?),
+                    name,
+                });
             } else {
                 break;
             }
@@ -439,7 +588,10 @@ impl Parser {
         if !self.check(TokenType::RIGHT_PAREN) {
             loop {
                 if arguments.len() >= 255 {
-                    return Err(Error::TooManyArguments(self.peek()));
+                    return Err(Error::TooManyArguments(
+                        self.peek(),
+                        ArgumentList::Arguments,
+                    ));
                 }
 
                 arguments.push(self.expression()?);
@@ -450,7 +602,7 @@ impl Parser {
             }
         }
 
-        let paren = self.consume(TokenType::RIGHT_PAREN, "Expect ')' after arguments.")?;
+        let paren = self.consume_after(TokenType::RIGHT_PAREN, "Expect ')' after arguments.")?;
 
         Ok(Expr::Call {
             callee: Box::new(callee),
@@ -475,22 +627,138 @@ impl Parser {
         }
 
         if self.matches(&[TokenType::IDENTIFIER]) {
-            return Ok(Expr::Variable(self.previous()));
+            return Ok(Expr::variable(self.previous()));
         }
 
         if self.matches(&[TokenType::LEFT_PAREN]) {
             let expr = self.expression();
-            self.consume(TokenType::RIGHT_PAREN, "Expect ')' after expression.")?;
+            self.consume_after(TokenType::RIGHT_PAREN, "Expect ')' after expression.")?;
             return Ok(Expr::Grouping(Box::new(expr?)));
         }
 
+        if self.matches(&[TokenType::LEFT_BRACKET]) {
+            return self.list();
+        }
+
+        if self.matches(&[TokenType::LEFT_BRACE]) {
+            return self.brace();
+        }
+
         Err(Error::ExpectExpression(self.peek()))?
     }
 
+    fn list(&mut self) -> Result<Expr> {
+        let mut elements = Vec::new();
+
+        if !self.check(TokenType::RIGHT_BRACKET) {
+            loop {
+                elements.push(self.expression()?);
+
+                if !self.matches(&[TokenType::COMMA]) {
+                    break;
+                }
+            }
+        }
+
+        let bracket =
+            self.consume_after(TokenType::RIGHT_BRACKET, "Expect ']' after list elements.")?;
+
+        Ok(Expr::ListLiteral { bracket, elements })
+    }
+
+    /// `{` in expression position is a map literal if it is empty or its first key is a
+    /// single literal or identifier followed by `:`, like `{"a": 1}` or `{x: 1}`. Otherwise
+    /// it is a block expression: statements followed by an expression without `;`, which is
+    /// the value of the block. Only two tokens are looked at, so a computed first key like
+    /// `{1 + 1: 2}` parses as a block and fails. Later keys may be any expression.
+    ///
+    /// Inside a block, `{` at statement position always starts a block statement, not a
+    /// value. So `{ {1} }` fails with "Expect ';' after expression." and then
+    /// "Expect expression.", while `{ ({1}) }` is a block with value `1`
+    fn brace(&mut self) -> Result<Expr> {
+        let is_map = self.check(TokenType::RIGHT_BRACE)
+            || (matches!(
+                self.peek().token_type,
+                TokenType::STRING
+                    | TokenType::IDENTIFIER
+                    | TokenType::NUMBER
+                    | TokenType::TRUE
+                    | TokenType::FALSE
+                    | TokenType::NIL
+            ) && self.check_next(TokenType::COLON));
+
+        if is_map {
+            self.map()
+        } else {
+            self.block_expression()
+        }
+    }
+
+    fn block_expression(&mut self) -> Result<Expr> {
+        let mut stmts = Vec::new();
+
+        loop {
+            if self.starts_statement() {
+                stmts.extend(self.declaration()?);
+                continue;
+            }
+
+            let expr = self.expression()?;
+
+            if !self.matches(&[TokenType::SEMICOLON]) {
+                self.consume_after(TokenType::RIGHT_BRACE, "Expect '}' after block value.")?;
+
+                return Ok(Expr::Block(stmts, Box::new(expr)));
+            }
+
+            stmts.push(Stmt::Expression(Box::new(expr)));
+        }
+    }
+
+    /// Whether next token can only start a statement, not an expression
+    fn starts_statement(&self) -> bool {
+        matches!(
+            self.peek().token_type,
+            TokenType::VAR
+                | TokenType::FUN
+                | TokenType::PRINT
+                | TokenType::IF
+                | TokenType::WHILE
+                | TokenType::FOR
+                | TokenType::RETURN
+                | TokenType::BREAK
+                | TokenType::CONTINUE
+                | TokenType::LEFT_BRACE
+        )
+    }
+
+    fn map(&mut self) -> Result<Expr> {
+        let mut entries = Vec::new();
+
+        if !self.check(TokenType::RIGHT_BRACE) {
+            loop {
+                let key = self.expression()?;
+                self.consume_after(TokenType::COLON, "Expect ':' after map key.")?;
+                let value = self.expression()?;
+
+                entries.push((key, value));
+
+                if !self.matches(&[TokenType::COMMA]) {
+                    break;
+                }
+            }
+        }
+
+        let brace = self.consume_after(TokenType::RIGHT_BRACE, "Expect '}' after map entries.")?;
+
+        Ok(Expr::MapLiteral { brace, entries })
+    }
+
     // endregion: --- Expressions
 
     // region:    --- Helpers
 
+    /// Error points at the token that didn't match
     fn consume(&mut self, token_type: TokenType, message: impl Into<String>) -> Result<Token> {
         if self.check(token_type) {
             return Ok(self.advance());
@@ -499,12 +767,31 @@ impl Parser {
         Err(Error::UnexpectedToken(self.peek(), message.into()))?
     }
 
-    fn synchronize(&mut self) -> () {
+    /// For "Expect X after Y." errors, which point at the token after which X was expected
+    fn consume_after(
+        &mut self,
+        token_type: TokenType,
+        message: impl Into<String>,
+    ) -> Result<Token> {
+        if self.check(token_type) {
+            return Ok(self.advance());
+        }
+
+        Err(Error::UnexpectedToken(self.previous(), message.into()))?
+    }
+
+    /// Skips tokens up to the next statement. Inside a block stops before its `}`,
+    /// so the block is closed where it really ends
+    fn synchronize(&mut self) {
+        if self.closes_block() {
+            return;
+        }
+
         self.advance();
 
         while !self.is_end() {
             {
-                if self.previous().token_type == TokenType::SEMICOLON {
+                if self.previous().token_type == TokenType::SEMICOLON || self.closes_block() {
                     return;
                 }
 
@@ -527,12 +814,20 @@ impl Parser {
         }
     }
 
+    fn closes_block(&self) -> bool {
+        self.blocks > 0 && self.check(TokenType::RIGHT_BRACE)
+    }
+
     fn is_end(&self) -> bool {
         self.peek().token_type == TokenType::EOF
     }
 
+    /// Never past the trailing EOF `new` guarantees, the fallback only keeps it bounds-safe
     fn peek(&self) -> Token {
-        self.tokens[self.current].clone()
+        match self.tokens.get(self.current) {
+            Some(token) => token.clone(),
+            None => Token::eof(self.tokens.last().map_or(1, |token| token.line)),
+        }
     }
 
     fn advance(&mut self) -> Token {
@@ -543,8 +838,15 @@ impl Parser {
         self.previous()
     }
 
+    /// Last consumed token, EOF if nothing was consumed yet
     fn previous(&self) -> Token {
-        self.tokens[self.current - 1].clone()
+        match self.current.checked_sub(1) {
+            Some(previous) => match self.tokens.get(previous) {
+                Some(token) => token.clone(),
+                None => self.peek(),
+            },
+            None => Token::eof(self.peek().line),
+        }
     }
 
     fn matches(&mut self, expected: &[TokenType]) -> bool {
@@ -566,32 +868,32 @@ impl Parser {
         self.peek().token_type == token_type
     }
 
+    /// Checks the token after the current one
+    fn check_next(&self, token_type: TokenType) -> bool {
+        if self.is_end() {
+            return false;
+        }
+
+        self.tokens
+            .get(self.current + 1)
+            .is_some_and(|token| token.token_type == token_type)
+    }
+
     // endregion: --- Helpers
 
     // region:    --- Error
 
     pub fn had_error(&self) -> bool {
-        self.had_error
+        !self.errors.is_empty()
     }
 
-    fn error(error: &Error) {
-        match error {
-            Error::UnknownExpression(token) => {
-                crate::report(token.line, "Unknown expression.");
-            }
-            Error::UnexpectedToken(token, message) => {
-                crate::report(token.line, message);
-            }
-            Error::ExpectExpression(token) => {
-                crate::report(token.line, format!("Expect expression."));
-            }
-            Error::InvalidAssignmentTarget(token) => {
-                crate::report(token.line, format!("Invalid assignment target."));
-            }
-            Error::TooManyArguments(token) => {
-                crate::report(token.line, format!("Can't have more than 255 arguments."));
-            }
-        }
+    pub fn errors(&self) -> &[Error] {
+        &self.errors
+    }
+
+    fn error(&mut self, error: Error) {
+        crate::report(error.token().line, error.message());
+        self.errors.push(error);
     }
 
     // endregion: --- Error
@@ -605,6 +907,7 @@ mod tests {
     type Result<T> = core::result::Result<T, Error>; // For tests.
 
     use super::*;
+    use crate::AstPrinter;
 
     #[test]
     fn test_parse_nil_ok() -> Result<()> {
@@ -706,6 +1009,445 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_parse_from_tokens_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut scanner = crate::Scanner::from_source("var a = 1;\nprint a;");
+        scanner.scan_tokens()?;
+        let fx_borrowed = Parser::new(scanner.tokens()).parse_stmt()?;
+
+        // -- Exec
+        let owned = Parser::from_tokens(scanner.into_tokens()).parse_stmt()?;
+
+        // -- Check
+        assert_eq!(owned, fx_borrowed);
+        assert_eq!(owned.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_expression_error_before_semicolon_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_cases = ["print (1 2;", "(1 2;"];
+
+        for source in fx_cases {
+            let mut scanner = crate::Scanner::from_source(source);
+            scanner.scan_tokens()?;
+
+            // -- Exec
+            let error = Parser::new(scanner.tokens())
+                .parse_stmt()
+                .expect_err("unclosed group must fail");
+
+            // -- Check
+            assert!(
+                matches!(
+                    &error,
+                    super::Error::UnexpectedToken(_, message) if message == "Expect ')' after expression."
+                ),
+                "{}: {:?}",
+                source,
+                error
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_brace_expression_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_cases = [
+            ("{}", true),
+            ("{ \"a\": 1, \"b\": 2 }", true),
+            ("{ 1: 2, 1 + 1: 3 }", true),
+            ("{ true: 1 }", true),
+            ("{ a: 1 }", true),
+            ("{ 1 }", false),
+            ("{ a }", false),
+            ("{ ({ 1 }) }", false),
+            ("{ var a = 1; a }", false),
+            ("{ print 1; a = 2; a }", false),
+        ];
+
+        for (source, is_map) in fx_cases {
+            let mut scanner = crate::Scanner::from_source(source);
+            scanner.scan_tokens()?;
+
+            // -- Exec
+            let expr = Parser::new(scanner.tokens()).parse_expr()?;
+
+            // -- Check
+            match expr {
+                Expr::MapLiteral { .. } => assert!(is_map, "{:?} must be a block", source),
+                Expr::Block(..) => assert!(!is_map, "{:?} must be a map", source),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_brace_block_in_block_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut scanner = crate::Scanner::from_source("var x = { {1} };");
+        scanner.scan_tokens()?;
+        let mut parser = Parser::new(scanner.tokens());
+
+        // -- Exec
+        let result = parser.parse_stmt();
+
+        // -- Check
+        assert!(result.is_err());
+        let messages = parser
+            .errors()
+            .iter()
+            .map(|error| error.message())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec!["Expect ';' after expression.", "Expect expression."]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_brace_computed_first_key_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut scanner = crate::Scanner::from_source("{ 1 + 1: 2 }");
+        scanner.scan_tokens()?;
+
+        // -- Exec & Check
+        assert!(Parser::new(scanner.tokens()).parse_expr().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_brace_deeply_nested_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        // Each level used to parse its contents twice, which took seconds at 20 levels
+        let depth = 40;
+        let source = format!("var x = {}1{};", "{ 1 + ".repeat(depth), " }".repeat(depth));
+        let mut scanner = crate::Scanner::from_source(source);
+        scanner.scan_tokens()?;
+
+        // -- Exec
+        let stmts = Parser::new(scanner.tokens()).parse_stmt()?;
+
+        // -- Check
+        assert_eq!(stmts.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_too_many_arguments_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let names = (0..256).map(|i| format!("a{}", i)).collect::<Vec<_>>();
+        let fx_cases = [
+            (
+                format!("f({});", names.join(", ")),
+                ArgumentList::Arguments,
+                "Can't have more than 255 arguments.",
+            ),
+            (
+                format!("fun f({}) {{}}", names.join(", ")),
+                ArgumentList::Parameters,
+                "Can't have more than 255 parameters.",
+            ),
+        ];
+
+        for (source, list, message) in fx_cases {
+            let mut scanner = crate::Scanner::from_source(source);
+            scanner.scan_tokens()?;
+
+            // -- Exec
+            let error = Parser::new(scanner.tokens())
+                .parse_stmt()
+                .expect_err("256 items must fail");
+
+            // -- Check
+            assert!(matches!(&error, super::Error::TooManyArguments(_, l) if *l == list));
+            assert_eq!(error.message(), message);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_without_eof_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_number = Token::new(TokenType::NUMBER, "1", Some(Value::Number(1.0)), 3);
+
+        // -- Exec & Check
+        assert!(Parser::new(&[]).parse_stmt()?.is_empty());
+        assert!(matches!(
+            Parser::new(&[]).parse_expr(),
+            Err(super::Error::ExpectExpression(_))
+        ));
+        assert_eq!(
+            Parser::new(std::slice::from_ref(&fx_number)).parse_expr()?,
+            Expr::Literal(Some(Value::Number(1.0)))
+        );
+        assert!(matches!(
+            Parser::new(&[fx_number]).parse_stmt(),
+            Err(super::Error::UnexpectedToken(token, _)) if token.line == 3
+        ));
+
+        let parser = Parser::new(&[]);
+        assert_eq!(parser.previous(), Token::eof(1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_arbitrary_tokens_no_panic_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let types = [
+            TokenType::LEFT_PAREN,
+            TokenType::RIGHT_PAREN,
+            TokenType::LEFT_BRACE,
+            TokenType::RIGHT_BRACE,
+            TokenType::LEFT_BRACKET,
+            TokenType::RIGHT_BRACKET,
+            TokenType::COLON,
+            TokenType::COMMA,
+            TokenType::DOT,
+            TokenType::MINUS,
+            TokenType::SEMICOLON,
+            TokenType::EQUAL,
+            TokenType::IDENTIFIER,
+            TokenType::NUMBER,
+            TokenType::FUN,
+            TokenType::VAR,
+            TokenType::FOR,
+            TokenType::RETURN,
+            TokenType::BREAK,
+            TokenType::EOF,
+        ];
+        let token = |token_type: &TokenType| {
+            let literal = match token_type {
+                TokenType::NUMBER => Some(Value::Number(1.0)),
+                _ => None,
+            };
+
+            Token::new(token_type.clone(), "x", literal, 1)
+        };
+
+        // -- Exec & Check
+        // Every stream of up to three tokens, with EOF missing, trailing or in the middle
+        assert!(Parser::new(&[Token::eof(1)]).parse_stmt()?.is_empty());
+        assert!(Parser::new(&[Token::eof(1)]).parse_expr().is_err());
+
+        for a in &types {
+            for b in &types {
+                for c in &types {
+                    let tokens = [token(a), token(b), token(c)];
+
+                    for len in 1..=tokens.len() {
+                        _ = Parser::new(&tokens[..len]).parse_stmt();
+                        _ = Parser::new(&tokens[..len]).parse_expr();
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_unclosed_call_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut scanner = crate::Scanner::from_source("f(1\n\n\n;");
+        scanner.scan_tokens()?;
+
+        // -- Exec
+        let result = Parser::new(scanner.tokens()).parse_stmt();
+
+        // -- Check
+        match result {
+            Err(super::Error::UnexpectedToken(token, message)) => {
+                assert_eq!(message, "Expect ')' after arguments.");
+                assert_eq!(token.line, 1);
+            }
+            other => panic!("expected unexpected token error, got {:?}", other),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_missing_token_before_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut scanner = crate::Scanner::from_source("fun f()\n\nprint 1;");
+        scanner.scan_tokens()?;
+
+        // -- Exec
+        let result = Parser::new(scanner.tokens()).parse_stmt();
+
+        // -- Check
+        match result {
+            Err(super::Error::UnexpectedToken(token, message)) => {
+                assert_eq!(message, "Expect '{' before function body.");
+                assert_eq!(token.line, 3);
+            }
+            other => panic!("expected unexpected token error, got {:?}", other),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_recovers_at_block_end_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut scanner = crate::Scanner::from_source("{\n  var = 1;\n  print 2;\n}\nprint 3;");
+        scanner.scan_tokens()?;
+        let mut parser = Parser::new(scanner.tokens());
+
+        // -- Exec
+        let result = parser.parse_stmt();
+
+        // -- Check
+        assert!(result.is_err());
+        assert_eq!(parser.errors().len(), 1);
+        assert_eq!(parser.errors()[0].token().line, 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_error_before_block_end_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut scanner = crate::Scanner::from_source("{ print 1 }\nprint 2;");
+        scanner.scan_tokens()?;
+        let mut parser = Parser::new(scanner.tokens());
+
+        // -- Exec
+        let result = parser.parse_stmt();
+
+        // -- Check
+        assert!(result.is_err());
+        assert_eq!(parser.errors().len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_trailing_tokens_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut scanner = crate::Scanner::from_source("1 + 2 3");
+        scanner.scan_tokens()?;
+
+        // -- Exec
+        let mut parser = Parser::new(scanner.tokens());
+        let error = parser.parse_expr().expect_err("trailing `3` must fail");
+
+        // -- Check
+        assert!(matches!(&error, super::Error::TrailingTokens(token) if &*token.lexeme == "3"));
+        assert_eq!(error.message(), "Unexpected trailing tokens.");
+        assert!(parser.had_error());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_precedence_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_cases = [
+            ("- - 3", "(- (- 3.0))"),
+            ("+3 - +a", "(- (+ 3.0) (+ a))"),
+            ("!!true", "(! (! true))"),
+            ("-a * b", "(* (- a) b)"),
+            ("1 + 2 * 3", "(+ 1.0 (* 2.0 3.0))"),
+            ("1 - 2 - 3", "(- (- 1.0 2.0) 3.0)"),
+            ("1 < 2 == true", "(== (< 1.0 2.0) true)"),
+            ("!a == b", "(== (! a) b)"),
+        ];
+
+        for (source, expected) in fx_cases {
+            let mut scanner = crate::Scanner::from_source(source);
+            scanner.scan_tokens()?;
+
+            // -- Exec
+            let expr = Parser::new(scanner.tokens()).parse_expr()?;
+
+            // -- Check
+            assert_eq!(AstPrinter.print(&expr), expected, "{}", source);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_property_chain_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_cases = [
+            ("a.b.c()", "(get (get a b) c)()"),
+            ("a.b = c.d", "(set a b (get c d))"),
+            ("a.b.c = 1", "(set (get a b) c 1.0)"),
+        ];
+
+        for (source, expected) in fx_cases {
+            let mut scanner = crate::Scanner::from_source(source);
+            scanner.scan_tokens()?;
+
+            // -- Exec
+            let expr = Parser::new(scanner.tokens()).parse_expr()?;
+
+            // -- Check
+            assert_eq!(AstPrinter.print(&expr), expected, "{}", source);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_assignment_targets_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let parse = |source: &str| -> Result<Vec<Stmt>> {
+            let mut scanner = crate::Scanner::from_source(source);
+            scanner.scan_tokens()?;
+
+            Ok(Parser::new(scanner.tokens()).parse_stmt()?)
+        };
+
+        // -- Exec
+        let stmts = parse("a[0] = 1;\nobj.x = 2;\na[0] = obj.x = 3;")?;
+
+        // -- Check
+        let exprs = stmts
+            .iter()
+            .map(|stmt| match stmt {
+                Stmt::Expression(expr) => expr.as_ref(),
+                other => panic!("expected expression statement, got {:?}", other),
+            })
+            .collect::<Vec<_>>();
+
+        assert!(matches!(exprs[0], Expr::SetIndex { .. }));
+        assert!(matches!(exprs[1], Expr::Set { name, .. } if &*name.lexeme == "x"));
+        match exprs[2] {
+            Expr::SetIndex { value, .. } => assert!(matches!(**value, Expr::Set { .. })),
+            other => panic!("expected chained assignment, got {:?}", other),
+        }
+
+        for source in ["1 = 2;", "f() = 1;", "(a) = 1;", "a + b = 1;", "a.b() = 1;"] {
+            assert!(
+                matches!(
+                    parse(source).map_err(|e| e.downcast::<super::Error>()),
+                    Err(Ok(e)) if matches!(*e, super::Error::InvalidAssignmentTarget(_))
+                ),
+                "{}",
+                source
+            );
+        }
+
+        Ok(())
+    }
 }
 
 // endregion: --- Tests
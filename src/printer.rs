@@ -8,13 +8,109 @@ impl AstPrinter {
     where
         A: for<'a> Acceptor<String, &'a AstPrinter>,
     {
-        acceptor.accept(&self)
+        acceptor.accept(self)
     }
 }
 
 impl Visitor<String> for &AstPrinter {
     fn visit(&self, acceptor: impl Acceptor<String, Self>) -> String {
-        acceptor.accept(&self)
+        acceptor.accept(self)
+    }
+}
+
+/// Prints tree back as Lox source code
+#[derive(Default, Clone)]
+pub struct SourcePrinter {
+    preserve_grouping: bool,
+}
+
+impl SourcePrinter {
+    /// When `preserve_grouping` is set, every `Expr::Grouping` is printed with parentheses,
+    /// otherwise parentheses are emitted only where precedence requires them
+    pub fn new(preserve_grouping: bool) -> SourcePrinter {
+        SourcePrinter { preserve_grouping }
+    }
+
+    pub fn preserve_grouping(&self) -> bool {
+        self.preserve_grouping
+    }
+
+    pub fn print<A>(&self, acceptor: &A) -> String
+    where
+        A: for<'a> Acceptor<String, &'a SourcePrinter>,
+    {
+        acceptor.accept(self)
+    }
+}
+
+impl Visitor<String> for &SourcePrinter {
+    fn visit(&self, acceptor: impl Acceptor<String, Self>) -> String {
+        acceptor.accept(self)
+    }
+}
+
+/// Prints tree as compact JSON. Every node is an object with `type` tag
+#[derive(Default, Clone)]
+pub struct JsonPrinter;
+
+impl JsonPrinter {
+    pub fn print<A>(&self, acceptor: &A) -> String
+    where
+        A: for<'a> Acceptor<String, &'a JsonPrinter>,
+    {
+        acceptor.accept(self)
+    }
+
+    /// Object from already serialized field values, `type` goes first
+    pub(crate) fn object(node_type: &str, fields: &[(&str, String)]) -> String {
+        let mut result = format!("{{\"type\":{}", Self::string(node_type));
+
+        for (name, value) in fields {
+            result.push(',');
+            result.push_str(&Self::string(name));
+            result.push(':');
+            result.push_str(value);
+        }
+
+        result.push('}');
+        result
+    }
+
+    pub(crate) fn array(items: impl IntoIterator<Item = String>) -> String {
+        format!("[{}]", items.into_iter().collect::<Vec<_>>().join(","))
+    }
+
+    pub(crate) fn string(s: &str) -> String {
+        let mut result = String::from('"');
+
+        for c in s.chars() {
+            match c {
+                '"' => result.push_str("\\\""),
+                '\\' => result.push_str("\\\\"),
+                '\n' => result.push_str("\\n"),
+                '\r' => result.push_str("\\r"),
+                '\t' => result.push_str("\\t"),
+                c if c.is_control() => result.push_str(&format!("\\u{:04x}", c as u32)),
+                c => result.push(c),
+            }
+        }
+
+        result.push('"');
+        result
+    }
+
+    pub(crate) fn number(n: f64) -> String {
+        if n.is_finite() {
+            n.to_string()
+        } else {
+            String::from("null")
+        }
+    }
+}
+
+impl Visitor<String> for &JsonPrinter {
+    fn visit(&self, acceptor: impl Acceptor<String, Self>) -> String {
+        acceptor.accept(self)
     }
 }
 
@@ -25,7 +121,7 @@ mod tests {
     type Error = Box<dyn std::error::Error>;
     type Result<T> = core::result::Result<T, Error>; // For tests.
 
-    use crate::{Expr, Token, TokenType, Value};
+    use crate::{Expr, Parser, Scanner, Token, TokenType, Value};
 
     use super::*;
 
@@ -35,7 +131,7 @@ mod tests {
         let expr = Expr::Literal(Some(Value::Number(123.0)));
 
         // -- Exec
-        let printer = AstPrinter::default();
+        let printer = AstPrinter;
         let result = printer.print(&expr);
 
         // -- Check
@@ -59,7 +155,7 @@ mod tests {
         };
 
         // -- Exec
-        let printer = AstPrinter::default();
+        let printer = AstPrinter;
         let result = printer.print(&expr);
 
         // -- Check
@@ -67,6 +163,170 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_print_logical_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let logical = |token_type: TokenType, lexeme: &str| Expr::Logical {
+            left: Box::new(Expr::Literal(Some(Value::Boolean(true)))),
+            operator: Token::new(token_type, lexeme, None, 1),
+            right: Box::new(Expr::Literal(Some(Value::Nil))),
+        };
+
+        // -- Exec
+        let printer = AstPrinter;
+        let and = printer.print(&logical(TokenType::AND, "and"));
+        let or = printer.print(&logical(TokenType::OR, "or"));
+
+        // -- Check
+        assert_eq!(and, "(and true nil)");
+        assert_eq!(or, "(or true nil)");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_print_else_if_chain_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_source = "if (a) print 1; else if (b) print 2; else print 3;";
+
+        let mut scanner = Scanner::from_source(fx_source);
+        scanner.scan_tokens()?;
+        let stmts = Parser::new(scanner.tokens()).parse_stmt()?;
+
+        // -- Exec
+        let result = AstPrinter.print(&stmts[0]);
+
+        // -- Check
+        assert_eq!(
+            result,
+            "if (a) {print 1.0} else if (b) {print 2.0} else {print 3.0}"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_print_loops_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_cases = [
+            ("while (a < 3) print a;", "while ((< a 3.0)) {print a}"),
+            (
+                "for (;i < 3; i = i + 1) print i;",
+                "while ((< i 3.0)) {print i} [i = (+ i 1.0)]",
+            ),
+            (
+                "outer: for (;;) break outer;",
+                "outer: while (true) {break outer}",
+            ),
+        ];
+
+        for (source, expected) in fx_cases {
+            let mut scanner = Scanner::from_source(source);
+            scanner.scan_tokens()?;
+            let stmts = Parser::new(scanner.tokens()).parse_stmt()?;
+
+            // -- Exec
+            let result = AstPrinter.print(&stmts[0]);
+
+            // -- Check
+            assert_eq!(result, expected, "{}", source);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_print_expr_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let expr = parse_expr("-a * (1 + \"x\\\") == nil")?;
+
+        // -- Exec
+        let result = JsonPrinter.print(&expr);
+
+        // -- Check
+        assert_eq!(
+            result,
+            concat!(
+                r#"{"type":"Binary","operator":"==","line":1,"#,
+                r#""left":{"type":"Binary","operator":"*","line":1,"#,
+                r#""left":{"type":"Unary","operator":"-","line":1,"#,
+                r#""right":{"type":"Variable","name":"a","line":1}},"#,
+                r#""right":{"type":"Grouping","expression":"#,
+                r#"{"type":"Binary","operator":"+","line":1,"#,
+                r#""left":{"type":"Literal","value":1},"#,
+                r#""right":{"type":"Literal","value":"x\\"}}}},"#,
+                r#""right":{"type":"Literal","value":null}}"#
+            )
+        );
+
+        Ok(())
+    }
+
+    fn parse_expr(source: &str) -> Result<Expr> {
+        let mut scanner = Scanner::from_source(source);
+        scanner.scan_tokens()?;
+
+        let mut parser = Parser::new(scanner.tokens());
+
+        Ok(parser.parse_expr()?)
+    }
+
+    #[test]
+    fn test_source_print_preserve_grouping_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let expr = parse_expr("((1 + 2))")?;
+
+        // -- Exec
+        let preserving = SourcePrinter::new(true).print(&expr);
+        let lisp = AstPrinter.print(&expr);
+
+        // -- Check
+        assert_eq!(preserving, "((1 + 2))");
+        assert_eq!(lisp, "(group (group (+ 1.0 2.0)))");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_source_print_drop_redundant_grouping_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let redundant = parse_expr("((1 + 2)) - (3 * 4)")?;
+        let required = parse_expr("(1 + 2) * -(3 - 4)")?;
+
+        // -- Exec
+        let printer = SourcePrinter::default();
+
+        // -- Check
+        assert_eq!(printer.print(&redundant), "1 + 2 - 3 * 4");
+        assert_eq!(printer.print(&required), "(1 + 2) * -(3 - 4)");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_source_print_program_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_source =
+            "fun add(a, b) {\n    if (a > b) return a;\n    return a + b;\n}\nprint add(1, \"2\");";
+
+        let mut scanner = Scanner::from_source(fx_source);
+        scanner.scan_tokens()?;
+        let stmts = Parser::new(scanner.tokens()).parse_stmt()?;
+
+        // -- Exec
+        let printer = SourcePrinter::default();
+        let result = stmts
+            .iter()
+            .map(|stmt| printer.print(stmt))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        // -- Check
+        assert_eq!(result, fx_source);
+
+        Ok(())
+    }
 }
 
 // endregion: --- Tests
@@ -6,7 +6,20 @@ pub type Result<T> = core::result::Result<T, Error>;
 pub enum Error {
     LocalVarReadWhileInitialized(Token),
     RedefiningLocalVar(Token),
+    DuplicateParameter(Token),
     TopLevelReturn(Token),
+    BreakOutsideLoop(Token),
+    ContinueOutsideLoop(Token),
+    /// `break name;` or `continue name;` without an enclosing `name:` loop
+    UnknownLabel(Token),
+}
+
+/// Problems that don't stop execution
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    UnusedLocalVar(Token),
+    /// Global declared again. Allowed in Lox, reported only if enabled
+    GlobalRedeclaration(Token),
 }
 
 // region:    --- Error Boilerplate
@@ -1,19 +1,34 @@
 mod error;
 
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 
-pub use error::{Error, Result};
+pub use error::{Error, Result, Warning};
 use tracing::info;
 
-use crate::{visitor::Acceptor, MutInterpreter, Stmt, Token, Visitor};
+use crate::{visitor::Acceptor, Expr, Node, Stmt, Token, Visitor};
 
 pub type MutResolver = Rc<RefCell<Resolver>>;
 
 pub struct Resolver {
-    interpreter: MutInterpreter,
-    pub scopes: Vec<HashMap<String, bool>>,
+    pub scopes: Vec<HashMap<Rc<str>, bool>>,
+    /// Declared local variables of each scope that were not read yet
+    unused: Vec<HashMap<Rc<str>, Token>>,
+    warnings: Vec<Warning>,
     current_function: FunctionType,
-    had_error: bool,
+    /// Labels of loops enclosing current statement within current function, innermost last
+    loops: Vec<Option<Rc<str>>>,
+    /// Treats warnings as errors
+    strict: bool,
+    /// Warns when a global is declared again
+    warn_global_redeclaration: bool,
+    /// Globals declared so far, tracked only if `warn_global_redeclaration` is on
+    globals: HashSet<Rc<str>>,
+    /// Every resolution error in source order, reported once resolution is done
+    errors: Vec<Error>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -22,18 +37,52 @@ pub enum FunctionType {
     Function,
 }
 
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Resolver {
-    pub fn new(interpreter: &MutInterpreter) -> Resolver {
+    pub fn new() -> Resolver {
         Resolver {
-            interpreter: interpreter.clone(),
             scopes: vec![],
+            unused: vec![],
+            warnings: vec![],
             current_function: FunctionType::None,
-            had_error: false,
+            loops: vec![],
+            strict: false,
+            warn_global_redeclaration: false,
+            globals: HashSet::new(),
+            errors: vec![],
         }
     }
 
+    /// Whether there were errors, or any warning in strict mode
     pub fn had_error(&self) -> bool {
-        self.had_error
+        !self.errors.is_empty() || (self.strict && !self.warnings.is_empty())
+    }
+
+    pub fn errors(&self) -> &[Error] {
+        &self.errors
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.errors.len()
+    }
+
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Makes every warning an error, so resolution fails. Off by default
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Warns on `var x; var x;` at top level. Redeclaration itself stays valid. Off by default
+    pub fn set_warn_global_redeclaration(&mut self, warn: bool) {
+        self.warn_global_redeclaration = warn;
     }
 
     pub fn current_function(&self) -> FunctionType {
@@ -44,6 +93,29 @@ impl Resolver {
         std::mem::replace(&mut self.current_function, replace)
     }
 
+    pub fn begin_loop(&mut self, label: Option<&Token>) {
+        self.loops.push(label.map(|label| label.lexeme.clone()));
+    }
+
+    pub fn end_loop(&mut self) {
+        self.loops.pop();
+    }
+
+    pub fn in_loop(&self) -> bool {
+        !self.loops.is_empty()
+    }
+
+    /// Whether any enclosing loop is labeled `label`
+    pub fn has_label(&self, label: &Token) -> bool {
+        self.loops
+            .iter()
+            .any(|name| name.as_deref() == Some(&*label.lexeme))
+    }
+
+    pub fn replace_loops(&mut self, replace: Vec<Option<Rc<str>>>) -> Vec<Option<Rc<str>>> {
+        std::mem::replace(&mut self.loops, replace)
+    }
+
     pub fn resolve(self, stmts: &[Stmt]) -> Result<bool> {
         info!("Resolving statements");
 
@@ -51,34 +123,42 @@ impl Resolver {
 
         Self::resolve_block(&resolver.clone(), stmts)?;
 
-        let had_error = resolver.borrow().had_error();
+        let resolver = resolver.borrow();
+        resolver.errors.iter().for_each(Self::report);
 
-        Ok(had_error)
+        Ok(resolver.had_error())
     }
 
     pub fn begin_scope(&mut self) {
         self.scopes.push(HashMap::new());
+        self.unused.push(HashMap::new());
     }
 
     pub fn end_scope(&mut self) {
         self.scopes.pop();
+
+        if let Some(unused) = self.unused.pop() {
+            let mut unused = unused.into_values().collect::<Vec<_>>();
+            unused.sort_by(|a, b| a.line.cmp(&b.line).then(a.lexeme.cmp(&b.lexeme)));
+
+            for token in unused {
+                self.warning(Warning::UnusedLocalVar(token));
+            }
+        }
     }
 
+    /// Errors of statements are collected, so the rest of the block is still resolved
     pub fn resolve_block(visitor: &MutResolver, stmts: &[Stmt]) -> Result<()> {
         for stmt in stmts {
-            match stmt.accept(visitor) {
-                Ok(_) => {}
-                Err(e) => {
-                    visitor.borrow_mut().had_error = true;
-                    Self::error(&e)
-                }
-            };
+            if let Err(e) = stmt.accept(visitor) {
+                visitor.borrow_mut().errors.push(e);
+            }
         }
 
         Ok(())
     }
 
-    fn error(e: &Error) {
+    fn report(e: &Error) {
         match e {
             Error::LocalVarReadWhileInitialized(token) => crate::report(
                 token.line,
@@ -88,9 +168,61 @@ impl Resolver {
                 token.line,
                 "Already a variable with this name in this scope",
             ),
+            Error::DuplicateParameter(token) => crate::report(
+                token.line,
+                "Already a variable with this name in this scope.",
+            ),
             Error::TopLevelReturn(token) => {
                 crate::report(token.line, "Can't return from top-level code")
             }
+            Error::BreakOutsideLoop(token) => {
+                crate::report(token.line, "Can't use 'break' outside of a loop.")
+            }
+            Error::ContinueOutsideLoop(token) => {
+                crate::report(token.line, "Can't use 'continue' outside of a loop.")
+            }
+            Error::UnknownLabel(token) => crate::report(
+                token.line,
+                format!("Undefined loop label '{}'.", token.lexeme),
+            ),
+        }
+    }
+
+    fn warning(&mut self, warning: Warning) {
+        let (line, message) = match &warning {
+            Warning::UnusedLocalVar(token) => (
+                token.line,
+                format!("Unused local variable '{}'.", token.lexeme),
+            ),
+            Warning::GlobalRedeclaration(token) => (
+                token.line,
+                format!("Global variable '{}' is already declared.", token.lexeme),
+            ),
+        };
+
+        if self.strict {
+            crate::report(line, message);
+        } else {
+            crate::report_warning(line, message);
+        }
+
+        self.warnings.push(warning);
+    }
+
+    /// Starts tracking local variable, so it is reported if never read until end of scope
+    pub fn track_usage(&mut self, name: &Token) {
+        if let Some(unused) = self.unused.last_mut() {
+            unused.insert(name.lexeme.clone(), name.clone());
+        }
+    }
+
+    /// Marks nearest variable with that name as read
+    pub fn mark_used(&mut self, name: &Token) {
+        for (i, scope) in self.scopes.iter().enumerate().rev() {
+            if scope.contains_key(&name.lexeme) {
+                self.unused[i].remove(&name.lexeme);
+                return;
+            }
         }
     }
 
@@ -101,6 +233,8 @@ impl Resolver {
             }
 
             scope.insert(name.lexeme.clone(), false);
+        } else if self.warn_global_redeclaration && !self.globals.insert(name.lexeme.clone()) {
+            self.warning(Warning::GlobalRedeclaration(name.clone()));
         }
 
         Ok(())
@@ -112,19 +246,40 @@ impl Resolver {
         }
     }
 
-    pub fn resolve_local(&mut self, name: &Token) {
-        if self.scopes.is_empty() {
-            return;
-        }
+    /// Stores count of scopes between use of `name` and its declaration into `distance`.
+    /// Names not found in any scope are globals and get `None`
+    pub fn resolve_local(&self, name: &Token, distance: &Cell<Option<usize>>) {
+        let found = self
+            .scopes
+            .iter()
+            .rev()
+            .position(|scope| scope.contains_key(&name.lexeme));
 
-        for (i, scope) in self.scopes.iter().enumerate().rev() {
-            let depth = self.scopes.len().checked_sub(i + 2).unwrap_or(0);
+        distance.set(found);
+    }
 
-            if scope.contains_key(&name.lexeme) {
-                self.interpreter.borrow_mut().resolve(name, depth);
-                return;
-            }
+    /// Depths stored by resolution, one `[line N] name -> depth` line per variable use
+    /// or assignment in source order. Unresolved names are shown as `global`
+    pub fn dump(stmts: &[Stmt]) -> String {
+        let mut lines = Vec::new();
+
+        for stmt in stmts {
+            stmt.walk(&mut |node| {
+                if let Node::Expr(
+                    Expr::Variable { name, distance } | Expr::Assign { name, distance, .. },
+                ) = node
+                {
+                    let depth = match distance.get() {
+                        Some(depth) => depth.to_string(),
+                        None => String::from("global"),
+                    };
+
+                    lines.push(format!("[line {}] {} -> {}", name.line, name.lexeme, depth));
+                }
+            });
         }
+
+        lines.join("\n")
     }
 }
 
@@ -136,3 +291,237 @@ impl Visitor<Result<()>> for &MutResolver {
         acceptor.accept(self)
     }
 }
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    type Error = Box<dyn std::error::Error>;
+    type Result<T> = core::result::Result<T, Error>; // For tests.
+
+    use crate::{Parser, Scanner};
+
+    use super::*;
+
+    fn resolve(source: &str) -> Result<MutResolver> {
+        let mut scanner = Scanner::from_source(source);
+        scanner.scan_tokens()?;
+
+        let stmts = Parser::new(scanner.tokens()).parse_stmt()?;
+
+        let resolver = Rc::new(RefCell::new(Resolver::new()));
+
+        Resolver::resolve_block(&resolver, &stmts)?;
+
+        Ok(resolver)
+    }
+
+    #[test]
+    fn test_loop_control_outside_loop_err() -> Result<()> {
+        assert!(resolve("break;")?.borrow().had_error());
+        assert!(resolve("if (true) continue;")?.borrow().had_error());
+        assert!(resolve("while (true) { fun f() { break; } }")?
+            .borrow()
+            .had_error());
+        assert!(
+            !resolve("while (true) { if (true) break; else continue; }")?
+                .borrow()
+                .had_error()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_loop_label_err() -> Result<()> {
+        assert!(resolve("while (true) { break outer; }")?
+            .borrow()
+            .had_error());
+        assert!(
+            resolve("outer: while (true) { fun f() { while (true) continue outer; } }")?
+                .borrow()
+                .had_error()
+        );
+        assert!(
+            !resolve("outer: while (true) { for (;;) { break outer; } continue outer; }")?
+                .borrow()
+                .had_error()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_top_level_return_in_for_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut scanner = Scanner::from_source("while (true) return;");
+        scanner.scan_tokens()?;
+        let stmts = Parser::new(scanner.tokens()).parse_stmt()?;
+        let resolver = Rc::new(RefCell::new(Resolver::new()));
+
+        // -- Exec & Check
+        assert!(matches!(
+            stmts[0].accept(&resolver),
+            Err(super::Error::TopLevelReturn(_))
+        ));
+        assert!(!resolver.borrow().in_loop());
+
+        // `for` is desugared into a block around a `while`, the return is still reported
+        assert!(resolve("for (;;) { return; }")?.borrow().had_error());
+        assert!(resolve("for (var i = 0; i < 1; i = i + 1) return;")?
+            .borrow()
+            .had_error());
+        assert!(!resolve("fun f() { for (;;) { return; } }")?
+            .borrow()
+            .had_error());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dump_depths_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut scanner = Scanner::from_source(
+            "var a = 1;\n{\n  var b = a;\n  fun f() { return b + a; }\n  b = 2;\n}",
+        );
+        scanner.scan_tokens()?;
+        let stmts = Parser::new(scanner.tokens()).parse_stmt()?;
+
+        // -- Exec
+        let had_error = Resolver::new().resolve(&stmts)?;
+        let dump = Resolver::dump(&stmts);
+
+        // -- Check
+        assert!(!had_error);
+        assert_eq!(
+            dump,
+            "[line 3] a -> global\n\
+             [line 4] b -> 1\n\
+             [line 4] a -> global\n\
+             [line 5] b -> 0"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_redefinition_global_ok_local_err() -> Result<()> {
+        assert!(!resolve("var x = 1; var x = 2; var x;")?
+            .borrow()
+            .had_error());
+        assert!(resolve("{ var x = 1; var x = 2; }")?.borrow().had_error());
+        assert!(!resolve("var x = 1; { var x = 2; print x; }")?
+            .borrow()
+            .had_error());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors_collected_in_order_err() -> Result<()> {
+        // -- Exec
+        let resolver = resolve("return 1;\n{\n  var a = 1;\n  var a = 2;\n  print a;\n}")?;
+        let resolver = resolver.borrow();
+
+        // -- Check
+        assert!(resolver.had_error());
+        assert_eq!(resolver.error_count(), 2);
+        assert!(matches!(
+            &resolver.errors()[0],
+            super::Error::TopLevelReturn(token) if token.line == 1
+        ));
+        assert!(matches!(
+            &resolver.errors()[1],
+            super::Error::RedefiningLocalVar(token) if token.line == 4
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_global_redeclaration_warning_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut scanner = Scanner::from_source("var x = 1; var y; { var x; print x; } var x = 2;");
+        scanner.scan_tokens()?;
+        let stmts = Parser::new(scanner.tokens()).parse_stmt()?;
+
+        for warn in [false, true] {
+            let resolver = Rc::new(RefCell::new(Resolver::new()));
+            resolver.borrow_mut().set_warn_global_redeclaration(warn);
+
+            // -- Exec
+            Resolver::resolve_block(&resolver, &stmts)?;
+
+            // -- Check
+            let resolver = resolver.borrow();
+            assert!(!resolver.had_error());
+
+            if warn {
+                assert_eq!(resolver.warnings().len(), 1);
+                assert!(matches!(
+                    &resolver.warnings()[0],
+                    Warning::GlobalRedeclaration(token) if &*token.lexeme == "x"
+                ));
+            } else {
+                assert!(resolver.warnings().is_empty());
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_parameter_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut scanner = Scanner::from_source("fun f(a, a) {}");
+        scanner.scan_tokens()?;
+        let stmts = Parser::new(scanner.tokens()).parse_stmt()?;
+
+        let resolver = Rc::new(RefCell::new(Resolver::new()));
+
+        // -- Exec
+        let result = stmts[0].accept(&resolver);
+
+        // -- Check
+        assert!(matches!(
+            result,
+            Err(super::Error::DuplicateParameter(token)) if &*token.lexeme == "a"
+        ));
+        assert!(resolver.borrow().scopes.is_empty());
+        assert_eq!(resolver.borrow().current_function(), FunctionType::None);
+
+        assert!(resolve("fun f(a, a) {}")?.borrow().had_error());
+        assert!(!resolve("fun f(a, b) {}")?.borrow().had_error());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unused_local_warning_ok() -> Result<()> {
+        let resolver = resolve("{ var a = 1; var b = 2; print b; }")?;
+
+        let resolver = resolver.borrow();
+        assert_eq!(resolver.warnings().len(), 1);
+        assert!(matches!(
+            &resolver.warnings()[0],
+            Warning::UnusedLocalVar(token) if &*token.lexeme == "a"
+        ));
+        assert!(!resolver.had_error());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_used_local_no_warning_ok() -> Result<()> {
+        let resolver = resolve(
+            "var g; { var a; a = 1; print a; }\n\
+             fun f(unused_param) { var c = 1; fun inner() { return c; } return inner; }",
+        )?;
+
+        assert!(resolver.borrow().warnings().is_empty());
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,557 @@
+//! Command pipelines used by the binary. Each returns an [`ExitStatus`] instead of
+//! exiting the process, so `main` is the only place that calls `process::exit`
+
+use std::{
+    cell::RefCell,
+    path::Path,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config, AstPrinter, Interpreter, JsonPrinter, Parser, Resolver, Result, Scanner, Stmt,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    Ok,
+    /// Missing arguments or unknown command, usage was printed
+    Usage,
+    ScanError,
+    ParseError,
+    ResolveError,
+    RuntimeError,
+    /// Source file is missing or is not UTF-8 text
+    InputError,
+}
+
+impl ExitStatus {
+    /// Process exit code, configured by `LOX_EXIT_CODE_*` variables
+    pub fn code(&self) -> i32 {
+        match self {
+            ExitStatus::Ok => 0,
+            ExitStatus::Usage => 64,
+            ExitStatus::ScanError | ExitStatus::ParseError | ExitStatus::ResolveError => {
+                config().EXIT_CODE_COMPILE_ERROR
+            }
+            ExitStatus::RuntimeError => config().EXIT_CODE_RUNTIME_ERROR,
+            ExitStatus::InputError => 66,
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        *self == ExitStatus::Ok
+    }
+}
+
+/// Command of the binary, named in [`COMMANDS`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Tokenize,
+    Parse,
+    Dump,
+    Evaluate,
+    Run,
+    Benchmark,
+}
+
+impl Command {
+    /// Command with given name, `None` if there is no such command
+    pub fn from_name(name: &str) -> Option<Command> {
+        COMMANDS
+            .iter()
+            .find(|(command_name, ..)| *command_name == name)
+            .map(|(_, _, command)| *command)
+    }
+}
+
+/// Commands of the binary with a short description, in the order they are listed
+pub const COMMANDS: [(&str, &str, Command); 6] = [
+    ("tokenize", "Print tokens of the file", Command::Tokenize),
+    ("parse", "Print AST of an expression", Command::Parse),
+    ("dump", "Print AST of a whole program", Command::Dump),
+    (
+        "evaluate",
+        "Evaluate an expression and print its value",
+        Command::Evaluate,
+    ),
+    ("run", "Run a program", Command::Run),
+    (
+        "benchmark",
+        "Run a program and report time of each phase",
+        Command::Benchmark,
+    ),
+];
+
+/// Flags of the binary with a short description
+pub const FLAGS: [(&str, &str); 6] = [
+    ("--ast-json", "parse: print AST as JSON"),
+    ("--strict", "run: fail on resolver warnings"),
+    (
+        "--time-limit=<seconds>",
+        "run: fail after given wall-clock time",
+    ),
+    ("--dump-resolver", "run: print resolved variable depths"),
+    ("--no-resolve", "run: skip the resolver, for debugging"),
+    ("--help", "print this help"),
+];
+
+/// Help text listing commands and flags, names are aligned in columns
+pub fn usage(program: &str) -> String {
+    let width = COMMANDS
+        .iter()
+        .map(|(name, ..)| name.len())
+        .chain(FLAGS.iter().map(|(name, _)| name.len()))
+        .max()
+        .unwrap_or_default();
+
+    let mut lines = vec![
+        format!("Usage: {} <command> <filename> [flags]", program),
+        String::new(),
+        String::from("Commands:"),
+    ];
+
+    lines.extend(
+        COMMANDS
+            .iter()
+            .map(|(name, description, _)| format!("  {:width$}  {}", name, description)),
+    );
+    lines.push(String::new());
+    lines.push(String::from("Flags:"));
+    lines.extend(
+        FLAGS
+            .iter()
+            .map(|(name, description)| format!("  {:width$}  {}", name, description)),
+    );
+
+    lines.join("\n")
+}
+
+/// Output format of `parse` command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AstFormat {
+    #[default]
+    Lisp,
+    Json,
+}
+
+pub fn tokenize(filename: impl AsRef<Path>) -> Result<ExitStatus> {
+    tokenize_scanner(Scanner::new(filename)?)
+}
+
+/// Same as [`tokenize`], for source text in memory
+pub fn tokenize_source(source: &str) -> Result<ExitStatus> {
+    tokenize_scanner(Scanner::from_source(source))
+}
+
+fn tokenize_scanner(mut scanner: Scanner) -> Result<ExitStatus> {
+    scanner.scan_tokens()?;
+
+    for token in scanner.tokens() {
+        println!("{}", token);
+    }
+
+    if scanner.had_error() {
+        return Ok(ExitStatus::ScanError);
+    }
+
+    Ok(ExitStatus::Ok)
+}
+
+pub fn parse(filename: impl AsRef<Path>) -> Result<ExitStatus> {
+    parse_as(filename, AstFormat::Lisp)
+}
+
+pub fn parse_as(filename: impl AsRef<Path>, format: AstFormat) -> Result<ExitStatus> {
+    parse_scanner(Scanner::new(filename)?, format)
+}
+
+/// Same as [`parse_as`], for source text in memory
+pub fn parse_source(source: &str, format: AstFormat) -> Result<ExitStatus> {
+    parse_scanner(Scanner::from_source(source), format)
+}
+
+fn parse_scanner(mut scanner: Scanner, format: AstFormat) -> Result<ExitStatus> {
+    scanner.scan_tokens()?;
+
+    if scanner.had_error() {
+        return Ok(ExitStatus::ScanError);
+    }
+
+    let mut parser = Parser::from_tokens(scanner.into_tokens());
+    let expr = parser.parse_expr();
+
+    match expr {
+        Ok(expr) => {
+            let result = match format {
+                AstFormat::Lisp => AstPrinter.print(&expr),
+                AstFormat::Json => JsonPrinter.print(&expr),
+            };
+
+            println!("{}", result);
+        }
+        Err(_) => return Ok(ExitStatus::ParseError),
+    }
+
+    Ok(ExitStatus::Ok)
+}
+
+/// Prints AST of a whole program, statement by statement, without running it
+pub fn dump(filename: impl AsRef<Path>) -> Result<ExitStatus> {
+    dump_scanner(Scanner::new(filename)?)
+}
+
+/// Same as [`dump`], for source text in memory
+pub fn dump_source(source: &str) -> Result<ExitStatus> {
+    dump_scanner(Scanner::from_source(source))
+}
+
+fn dump_scanner(mut scanner: Scanner) -> Result<ExitStatus> {
+    scanner.scan_tokens()?;
+
+    if scanner.had_error() {
+        return Ok(ExitStatus::ScanError);
+    }
+
+    let mut parser = Parser::from_tokens(scanner.into_tokens());
+    let stmts = parser.parse_stmt();
+
+    if parser.had_error() {
+        return Ok(ExitStatus::ParseError);
+    }
+
+    // Blocks are printed over several lines, blank ones are skipped
+    for stmt in stmts? {
+        AstPrinter
+            .print(&stmt)
+            .lines()
+            .filter(|line| !line.is_empty())
+            .for_each(|line| println!("{}", line));
+    }
+
+    Ok(ExitStatus::Ok)
+}
+
+pub fn evaluate(filename: impl AsRef<Path>) -> Result<ExitStatus> {
+    evaluate_scanner(Scanner::new(filename)?)
+}
+
+/// Same as [`evaluate`], for source text in memory
+pub fn evaluate_source(source: &str) -> Result<ExitStatus> {
+    evaluate_scanner(Scanner::from_source(source))
+}
+
+fn evaluate_scanner(mut scanner: Scanner) -> Result<ExitStatus> {
+    scanner.scan_tokens()?;
+
+    if scanner.had_error() {
+        return Ok(ExitStatus::ScanError);
+    }
+
+    let mut parser = Parser::from_tokens(scanner.into_tokens());
+    let expr = parser.parse_expr();
+
+    if parser.had_error() {
+        return Ok(ExitStatus::ParseError);
+    }
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.interpret_expr(expr?);
+
+    if interpreter.had_runtime_error() {
+        return Ok(ExitStatus::RuntimeError);
+    }
+
+    match result {
+        Ok(value) => {
+            println!("{}", value.stringify());
+        }
+        Err(_) => return Ok(ExitStatus::RuntimeError),
+    }
+
+    Ok(ExitStatus::Ok)
+}
+
+/// Settings of the `run` command
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RunOptions {
+    /// Resolver warnings fail the program as resolve errors
+    pub strict: bool,
+    /// Wall-clock limit, exceeding it is a runtime error
+    pub time_limit: Option<Duration>,
+    /// Prints resolved variable depths to stderr before running
+    pub dump_resolver: bool,
+    /// Skips the resolver, for debugging the interpreter alone. Every variable is then
+    /// read and assigned in globals, so locals and closures don't work as in Lox
+    pub no_resolve: bool,
+}
+
+pub fn run(filename: impl AsRef<Path>) -> Result<ExitStatus> {
+    run_with(filename, RunOptions::default())
+}
+
+pub fn run_with(filename: impl AsRef<Path>, options: RunOptions) -> Result<ExitStatus> {
+    run_scanner(Scanner::new(filename)?, options)
+}
+
+/// Same as [`run_with`], for source text in memory
+pub fn run_source(source: &str, options: RunOptions) -> Result<ExitStatus> {
+    run_scanner(Scanner::from_source(source), options)
+}
+
+fn run_scanner(mut scanner: Scanner, options: RunOptions) -> Result<ExitStatus> {
+    scanner.scan_tokens()?;
+
+    if scanner.had_error() {
+        return Ok(ExitStatus::ScanError);
+    }
+
+    let mut parser = Parser::from_tokens(scanner.into_tokens());
+    let stmts = parser.parse_stmt();
+
+    if parser.had_error() {
+        return Ok(ExitStatus::ParseError);
+    }
+
+    let mut stmts = stmts?;
+    prepare(&mut stmts);
+
+    let interpreter = Rc::new(RefCell::new(Interpreter::default()));
+
+    if !options.no_resolve {
+        let mut resolver = Resolver::new();
+        resolver.set_strict(options.strict);
+
+        if resolver.resolve(&stmts)? {
+            return Ok(ExitStatus::ResolveError);
+        }
+    }
+
+    if options.dump_resolver {
+        eprintln!("{}", Resolver::dump(&stmts));
+    }
+
+    interpreter.borrow_mut().set_time_limit(options.time_limit);
+    _ = interpreter.borrow_mut().interpret_stmt(&stmts);
+
+    if interpreter.borrow().had_runtime_error() {
+        return Ok(ExitStatus::RuntimeError);
+    }
+
+    Ok(ExitStatus::Ok)
+}
+
+/// Tree passes between parsing and resolving, so `run` and `benchmark` execute the same tree
+fn prepare(stmts: &mut [Stmt]) {
+    stmts.iter_mut().for_each(Stmt::fold_strings);
+}
+
+/// Runs a program and reports duration of each phase to stderr. Parsing includes [`prepare`]
+pub fn benchmark(filename: impl AsRef<Path>) -> Result<ExitStatus> {
+    let start = Instant::now();
+
+    let mut scanner = Scanner::new(filename)?;
+    scanner.scan_tokens()?;
+
+    let scanning = start.elapsed();
+
+    if scanner.had_error() {
+        return Ok(ExitStatus::ScanError);
+    }
+
+    let start = Instant::now();
+
+    let mut parser = Parser::from_tokens(scanner.into_tokens());
+    let stmts = parser.parse_stmt();
+
+    if parser.had_error() {
+        return Ok(ExitStatus::ParseError);
+    }
+
+    let mut stmts = stmts?;
+    prepare(&mut stmts);
+
+    let parsing = start.elapsed();
+
+    let start = Instant::now();
+
+    let interpreter = Rc::new(RefCell::new(Interpreter::default()));
+    let resolver = Resolver::new();
+    let had_error = resolver.resolve(&stmts)?;
+
+    let resolving = start.elapsed();
+
+    if had_error {
+        return Ok(ExitStatus::ResolveError);
+    }
+
+    let start = Instant::now();
+
+    _ = interpreter.borrow_mut().interpret_stmt(&stmts);
+
+    let interpreting = start.elapsed();
+
+    eprintln!("Scanning:     {:?}", scanning);
+    eprintln!("Parsing:      {:?}", parsing);
+    eprintln!("Resolving:    {:?}", resolving);
+    eprintln!("Interpreting: {:?}", interpreting);
+
+    if interpreter.borrow().had_runtime_error() {
+        return Ok(ExitStatus::RuntimeError);
+    }
+
+    Ok(ExitStatus::Ok)
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    type Error = Box<dyn std::error::Error>;
+    type Result<T> = core::result::Result<T, Error>; // For tests.
+
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+
+    fn write_program(name: &str, source: &str) -> Result<PathBuf> {
+        let path =
+            std::env::temp_dir().join(format!("lox-runner-{}-{}.lox", std::process::id(), name));
+
+        fs::write(&path, source)?;
+
+        Ok(path)
+    }
+
+    fn status_of(
+        name: &str,
+        source: &str,
+        command: fn(PathBuf) -> crate::Result<ExitStatus>,
+    ) -> Result<ExitStatus> {
+        let path = write_program(name, source)?;
+        let status = command(path.clone());
+        fs::remove_file(&path)?;
+
+        Ok(status?)
+    }
+
+    #[test]
+    fn test_status_ok() -> Result<()> {
+        assert_eq!(status_of("tokenize-ok", "1 + 2", tokenize)?, ExitStatus::Ok);
+        assert_eq!(status_of("parse-ok", "1 + 2", parse)?, ExitStatus::Ok);
+        assert_eq!(status_of("evaluate-ok", "1 + 2", evaluate)?, ExitStatus::Ok);
+        assert_eq!(status_of("run-ok", "var a = 1;", run)?, ExitStatus::Ok);
+        assert_eq!(ExitStatus::Ok.code(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_status_scan_error() -> Result<()> {
+        assert_eq!(
+            status_of("tokenize-scan", "@", tokenize)?,
+            ExitStatus::ScanError
+        );
+        assert_eq!(status_of("run-scan", "@", run)?, ExitStatus::ScanError);
+        assert_eq!(ExitStatus::ScanError.code(), 65);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_status_parse_error() -> Result<()> {
+        assert_eq!(
+            status_of("parse-parse", "(1 +", parse)?,
+            ExitStatus::ParseError
+        );
+        assert_eq!(
+            status_of("run-parse", "print ;", run)?,
+            ExitStatus::ParseError
+        );
+        assert_eq!(ExitStatus::ParseError.code(), 65);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_status_resolve_error() -> Result<()> {
+        assert_eq!(
+            status_of("benchmark-resolve", "return 1;", benchmark)?,
+            ExitStatus::ResolveError
+        );
+        assert_eq!(ExitStatus::ResolveError.code(), 65);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_status_strict_warning_error() -> Result<()> {
+        let source = "{ var unused = 1; }";
+
+        assert_eq!(status_of("run-lenient", source, run)?, ExitStatus::Ok);
+        assert_eq!(
+            status_of("run-strict", source, |path| run_with(
+                path,
+                RunOptions {
+                    strict: true,
+                    ..Default::default()
+                }
+            ))?,
+            ExitStatus::ResolveError
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_status_runtime_error() -> Result<()> {
+        assert_eq!(
+            status_of("evaluate-runtime", "-true", evaluate)?,
+            ExitStatus::RuntimeError
+        );
+        assert_eq!(
+            status_of("run-runtime", "print -true;", run)?,
+            ExitStatus::RuntimeError
+        );
+        assert_eq!(ExitStatus::RuntimeError.code(), 70);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_status_from_source_ok() -> Result<()> {
+        assert_eq!(evaluate_source("1 + 2")?, ExitStatus::Ok);
+        assert_eq!(evaluate_source("\"a\" + \"b\"")?, ExitStatus::Ok);
+        assert_eq!(evaluate_source("-true")?, ExitStatus::RuntimeError);
+        assert_eq!(evaluate_source("(1 +")?, ExitStatus::ParseError);
+        assert_eq!(evaluate_source("@")?, ExitStatus::ScanError);
+
+        assert_eq!(tokenize_source("var a;")?, ExitStatus::Ok);
+        assert_eq!(parse_source("1 + 2", AstFormat::Json)?, ExitStatus::Ok);
+        assert_eq!(dump_source("print 1;")?, ExitStatus::Ok);
+        assert_eq!(
+            run_source("return 1;", RunOptions::default())?,
+            ExitStatus::ResolveError
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prepare_folds_strings_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut scanner = Scanner::from_source("print \"a\" + \"b\";");
+        scanner.scan_tokens()?;
+        let mut stmts = Parser::from_tokens(scanner.into_tokens()).parse_stmt()?;
+
+        // -- Exec
+        prepare(&mut stmts);
+
+        // -- Check
+        assert_eq!(AstPrinter.print(&stmts[0]), "print ab");
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,50 @@
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    UnexpectedCharacter {
+        line: usize,
+        column: usize,
+        character: char,
+    },
+    /// Source ended before the closing `"`, `line` and `column` are where it ended
+    UnterminatedString { line: usize, column: usize },
+}
+
+impl Error {
+    /// Message as shown to the user, without the line prefix
+    pub fn message(&self) -> String {
+        match self {
+            Error::UnexpectedCharacter { character, .. } => {
+                format!("Unexpected character: {}", character)
+            }
+            Error::UnterminatedString { .. } => String::from("Unterminated string."),
+        }
+    }
+
+    pub fn line(&self) -> usize {
+        match self {
+            Error::UnexpectedCharacter { line, .. } | Error::UnterminatedString { line, .. } => {
+                *line
+            }
+        }
+    }
+
+    /// 1-based, counted in chars
+    pub fn column(&self) -> usize {
+        match self {
+            Error::UnexpectedCharacter { column, .. }
+            | Error::UnterminatedString { column, .. } => *column,
+        }
+    }
+}
+
+// region:    --- Error Boilerplate
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        write!(fmt, "{self:?}")
+    }
+}
+
+impl std::error::Error for Error {}
+
+// endregion: --- Error Boilerplate
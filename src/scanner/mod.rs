@@ -1,34 +1,28 @@
-use std::collections::HashMap;
-use std::{fs, path::Path};
+mod error;
+
+pub use error::Error;
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::{fs, io::Read, path::Path};
 
 use tracing::info;
 
 use crate::extensions::{CharExt, StringExt};
 use crate::Token;
 use crate::Value;
-use crate::{report, Result, TokenType};
+use crate::{config, report, snippet, Result, TokenType};
 use lazy_static::lazy_static;
 
 lazy_static! {
     static ref KEYWORDS: HashMap<&'static str, TokenType> = {
         let mut hm = HashMap::new();
 
-        hm.insert("and", TokenType::AND);
-        hm.insert("class", TokenType::CLASS);
-        hm.insert("else", TokenType::ELSE);
-        hm.insert("false", TokenType::FALSE);
-        hm.insert("for", TokenType::FOR);
-        hm.insert("fun", TokenType::FUN);
-        hm.insert("if", TokenType::IF);
-        hm.insert("nil", TokenType::NIL);
-        hm.insert("or", TokenType::OR);
-        hm.insert("print", TokenType::PRINT);
-        hm.insert("return", TokenType::RETURN);
-        hm.insert("super", TokenType::SUPER);
-        hm.insert("this", TokenType::THIS);
-        hm.insert("true", TokenType::TRUE);
-        hm.insert("var", TokenType::VAR);
-        hm.insert("while", TokenType::WHILE);
+        for token_type in TokenType::KEYWORDS {
+            if let Some(keyword) = token_type.keyword_str() {
+                hm.insert(keyword, token_type);
+            }
+        }
 
         hm
     };
@@ -37,43 +31,86 @@ lazy_static! {
 #[derive(Debug, Default)]
 pub struct Scanner {
     source: String,
+    /// Count of chars in `source`. Positions are char indices, not byte offsets
+    length: usize,
     start: usize,
     current: usize,
     line: usize,
+    /// Position where the current line begins, columns are counted from it
+    line_start: usize,
     tokens: Vec<Token>,
-    had_error: bool,
+    errors: Vec<Error>,
+    /// Lexemes seen so far, repeated identifiers share one allocation
+    symbols: HashSet<Rc<str>>,
+}
+
+/// Everything scanning produced. Tokens are kept even if there are errors
+#[derive(Debug, Default)]
+pub struct ScanResult {
+    pub tokens: Vec<Token>,
+    pub errors: Vec<Error>,
 }
 
 impl Scanner {
     /// Create a new scanner from source
     pub fn from_source(source: impl Into<String>) -> Scanner {
+        let source = source.into();
+
         Scanner {
-            source: source.into(),
+            length: source.chars().count(),
+            source,
             line: 1,
             ..Default::default()
         }
     }
 
-    /// Create a new scanner from a file
+    /// Create a new scanner from a file. A file that is not UTF-8 text is reported by name
+    /// instead of as a bare io error, so it is not confused with a missing file
     pub fn new(path: impl AsRef<Path>) -> Result<Scanner> {
-        Ok(Scanner {
-            source: fs::read_to_string(path)?,
-            line: 1,
-            ..Default::default()
-        })
+        let path = path.as_ref();
+        let source = String::from_utf8(fs::read(path)?).map_err(|_| {
+            crate::Error::ProgramExecutionError(format!(
+                "File '{}' is not valid UTF-8.",
+                path.display()
+            ))
+        })?;
+
+        Ok(Self::from_source(source))
+    }
+
+    /// Create a new scanner from anything readable, like piped stdin. Whole input is read
+    /// into memory first, tokens are not produced while reading
+    pub fn from_reader(mut reader: impl Read) -> Result<Scanner> {
+        let mut source = String::new();
+        reader.read_to_string(&mut source)?;
+
+        Ok(Self::from_source(source))
     }
 
     pub fn had_error(&self) -> bool {
-        self.had_error
+        !self.errors.is_empty()
     }
 
-    fn error(&mut self, message: String) {
-        self.had_error = true;
-        report(self.line, message);
+    pub fn errors(&self) -> &[Error] {
+        &self.errors
+    }
+
+    fn error(&mut self, error: Error) {
+        report(error.line(), error.message());
+
+        if config().SHOW_SOURCE {
+            eprintln!("{}", snippet(&self.source, error.line(), error.column()));
+        }
+
+        self.errors.push(error);
+    }
+
+    fn column(&self, position: usize) -> usize {
+        position - self.line_start + 1
     }
 
     fn is_end(&self) -> bool {
-        self.current >= self.source.len()
+        self.current >= self.length
     }
 
     fn advance(&mut self) -> char {
@@ -93,7 +130,7 @@ impl Scanner {
     }
 
     fn peek_next(&mut self) -> char {
-        if self.current + 1 >= self.source.len() {
+        if self.current + 1 >= self.length {
             return '\0';
         }
 
@@ -105,12 +142,23 @@ impl Scanner {
     }
 
     fn add_token_literal(&mut self, token_type: TokenType, literal: Option<Value>) {
-        let lexeme = self.source.substring(self.start, self.current);
+        let lexeme = self.intern(self.source.substring(self.start, self.current));
 
         self.tokens
             .push(Token::new(token_type, lexeme, literal, self.line));
     }
 
+    fn intern(&mut self, lexeme: String) -> Rc<str> {
+        if let Some(symbol) = self.symbols.get(lexeme.as_str()) {
+            return symbol.clone();
+        }
+
+        let symbol: Rc<str> = Rc::from(lexeme);
+        self.symbols.insert(symbol.clone());
+
+        symbol
+    }
+
     fn scan_token(&mut self) -> Result<()> {
         let c = self.advance();
 
@@ -119,7 +167,11 @@ impl Scanner {
             ')' => self.add_token(TokenType::RIGHT_PAREN),
             '{' => self.add_token(TokenType::LEFT_BRACE),
             '}' => self.add_token(TokenType::RIGHT_BRACE),
+            '[' => self.add_token(TokenType::LEFT_BRACKET),
+            ']' => self.add_token(TokenType::RIGHT_BRACKET),
+            ':' => self.add_token(TokenType::COLON),
             ',' => self.add_token(TokenType::COMMA),
+            '.' if self.peek().is_ascii_digit() => self.number()?,
             '.' => self.add_token(TokenType::DOT),
             '-' => self.add_token(TokenType::MINUS),
             '+' => self.add_token(TokenType::PLUS),
@@ -158,10 +210,21 @@ impl Scanner {
                 };
                 self.add_token(token)
             }
+            '?' => {
+                if self.expect('?') {
+                    self.add_token(TokenType::QUESTION_QUESTION)
+                } else {
+                    self.error(Error::UnexpectedCharacter {
+                        line: self.line,
+                        column: self.column(self.start),
+                        character: c,
+                    })
+                }
+            }
             '/' => {
                 if self.expect('/') {
                     // A comment goes until the end of the line
-                    while self.source.char_at(self.current) != '\n' && !self.is_end() {
+                    while self.peek() != '\n' && !self.is_end() {
                         self.advance();
                     }
                 } else {
@@ -174,6 +237,7 @@ impl Scanner {
             '\t' => {}
             '\n' => {
                 self.line += 1;
+                self.line_start = self.current;
             }
             '"' => self.string(),
 
@@ -183,7 +247,11 @@ impl Scanner {
                 } else if other.is_alpha() {
                     self.identifier();
                 } else {
-                    self.error(format!("Unexpected character: {}", c))
+                    self.error(Error::UnexpectedCharacter {
+                        line: self.line,
+                        column: self.column(self.start),
+                        character: c,
+                    })
                 }
             }
         }
@@ -205,17 +273,21 @@ impl Scanner {
         self.add_token(token_type);
     }
 
+    /// `5.5` and `.5` are numbers. Trailing dot is not part of a number, `5.` is `5`
+    /// followed by `DOT`, so property access stays possible after numbers
     fn number(&mut self) -> Result<()> {
-        while self.peek().is_digit(10) {
+        let leading_dot = self.source.char_at(self.start) == '.';
+
+        while self.peek().is_ascii_digit() {
             self.advance();
         }
 
-        // Look for a fractional part
-        if self.peek() == '.' && self.peek_next().is_digit(10) {
+        // Look for a fractional part, `.5` already has it
+        if !leading_dot && self.peek() == '.' && self.peek_next().is_ascii_digit() {
             // Consume the "."
             self.advance();
 
-            while self.peek().is_digit(10) {
+            while self.peek().is_ascii_digit() {
                 self.advance();
             }
         };
@@ -231,12 +303,16 @@ impl Scanner {
         while self.peek() != '"' && !self.is_end() {
             if self.peek() == '\n' {
                 self.line += 1;
+                self.line_start = self.current + 1;
             }
             self.advance();
         }
 
         if self.is_end() {
-            self.error("Unterminated string.".to_string());
+            self.error(Error::UnterminatedString {
+                line: self.line,
+                column: self.column(self.current),
+            });
             return;
         }
 
@@ -277,6 +353,21 @@ impl Scanner {
     pub fn tokens(&self) -> &[Token] {
         &self.tokens
     }
+
+    /// Takes scanned tokens without cloning them
+    pub fn into_tokens(self) -> Vec<Token> {
+        self.tokens
+    }
+
+    /// Scans whole source, returning tokens and errors together
+    pub fn scan(mut self) -> Result<ScanResult> {
+        self.scan_tokens()?;
+
+        Ok(ScanResult {
+            tokens: self.tokens,
+            errors: self.errors,
+        })
+    }
 }
 
 // region:    --- Tests
@@ -287,6 +378,91 @@ mod tests {
     type Error = Box<dyn std::error::Error>;
     type Result<T> = core::result::Result<T, Error>; // For tests.
 
+    #[test]
+    fn test_new_invalid_utf8_err() -> Result<()> {
+        // Fixtures
+        let fx_path =
+            std::env::temp_dir().join(format!("lox-scanner-{}-binary.lox", std::process::id()));
+        fs::write(&fx_path, [b'v', b'a', b'r', 0xff, 0xfe])?;
+
+        // Init
+        let result = Scanner::new(&fx_path);
+        fs::remove_file(&fx_path)?;
+
+        // Check
+        match result {
+            Err(crate::Error::ProgramExecutionError(message)) => {
+                assert!(
+                    message.contains(&fx_path.display().to_string()),
+                    "{}",
+                    message
+                );
+                assert!(message.contains("is not valid UTF-8"), "{}", message);
+            }
+            other => panic!("expected a UTF-8 error, got {:?}", other),
+        }
+        assert!(matches!(
+            Scanner::new(&fx_path),
+            Err(crate::Error::IoError(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unicode_identifier_ok() -> Result<()> {
+        // Fixtures
+        let fx_content = "var café = \"ü\"; имя_2";
+        let fx_tokens = vec![
+            "VAR var null",
+            "IDENTIFIER café null",
+            "EQUAL = null",
+            "STRING \"ü\" ü",
+            "SEMICOLON ; null",
+            "IDENTIFIER имя_2 null",
+            "EOF  null",
+        ];
+
+        // Init
+        let mut scanner = Scanner::from_source(fx_content);
+
+        scanner.scan_tokens()?;
+
+        let tokens = scanner.tokens();
+
+        // Check
+        assert!(!scanner.had_error());
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|t| t.to_string())
+                .collect::<Vec<String>>(),
+            fx_tokens
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_reader_ok() -> Result<()> {
+        // Fixtures
+        let fx_content = "var a = 1.5;\nprint a + \"b\";";
+
+        // Init
+        let mut from_reader = Scanner::from_reader(fx_content.as_bytes())?;
+        let mut from_source = Scanner::from_source(fx_content);
+
+        from_reader.scan_tokens()?;
+        from_source.scan_tokens()?;
+
+        // Check
+        assert!(!from_reader.had_error());
+        assert_eq!(from_reader.tokens(), from_source.tokens());
+        assert!(Scanner::from_reader(&[0xffu8, 0xfe][..]).is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_empty_file_ok() -> Result<()> {
         // Fixtures
@@ -401,6 +577,33 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_comment_at_eof_ok() -> Result<()> {
+        // Fixtures
+        let fx_contents = ["// comment", "42 // комментарий", "// ✓\n"];
+        let fx_lines = [1, 1, 2];
+
+        for (fx_content, fx_line) in fx_contents.into_iter().zip(fx_lines) {
+            // Init
+            let mut scanner = Scanner::from_source(fx_content);
+
+            scanner.scan_tokens()?;
+
+            let tokens = scanner.tokens();
+
+            // Check
+            assert!(!scanner.had_error());
+            assert_eq!(
+                tokens.last().map(|t| t.to_string()),
+                Some("EOF  null".into())
+            );
+            assert_eq!(tokens.last().map(|t| t.line), Some(fx_line));
+            assert!(tokens.iter().all(|t| !t.lexeme.contains("comment")));
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_number_ok() -> Result<()> {
         // Fixtures
@@ -427,6 +630,47 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_number_dots_ok() -> Result<()> {
+        // Fixtures
+        let fx_cases = [
+            (".5", vec!["NUMBER .5 0.5", "EOF  null"]),
+            ("5.", vec!["NUMBER 5 5.0", "DOT . null", "EOF  null"]),
+            ("5.5", vec!["NUMBER 5.5 5.5", "EOF  null"]),
+            (".5.5", vec!["NUMBER .5 0.5", "NUMBER .5 0.5", "EOF  null"]),
+            (
+                "a.b",
+                vec![
+                    "IDENTIFIER a null",
+                    "DOT . null",
+                    "IDENTIFIER b null",
+                    "EOF  null",
+                ],
+            ),
+        ];
+
+        for (fx_content, fx_tokens) in fx_cases {
+            // Init
+            let mut scanner = Scanner::from_source(fx_content);
+
+            scanner.scan_tokens()?;
+
+            // Check
+            assert_eq!(
+                scanner
+                    .tokens()
+                    .iter()
+                    .map(|t| t.to_string())
+                    .collect::<Vec<String>>(),
+                fx_tokens,
+                "{}",
+                fx_content
+            );
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_string_ok() -> Result<()> {
         // Fixtures
@@ -509,7 +753,7 @@ mod tests {
         let tokens = scanner.tokens();
 
         // Check
-        assert!(scanner.had_error);
+        assert!(scanner.had_error());
         assert_eq!(tokens.len(), fx_tokens.len());
 
         assert_eq!(
@@ -558,6 +802,77 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_interned_lexemes_ok() -> Result<()> {
+        // Fixtures
+        let fx_content = "foo = foo + bar;";
+
+        // Init
+        let mut scanner = Scanner::from_source(fx_content);
+
+        scanner.scan_tokens()?;
+
+        let tokens = scanner.tokens();
+
+        // Check
+        assert!(Rc::ptr_eq(&tokens[0].lexeme, &tokens[2].lexeme));
+        assert!(!Rc::ptr_eq(&tokens[0].lexeme, &tokens[4].lexeme));
+        assert_eq!(&*tokens[2].lexeme, "foo");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_result_err() -> Result<()> {
+        // Fixtures
+        let fx_content = "@foo";
+
+        // Init
+        let result = Scanner::from_source(fx_content).scan()?;
+
+        // Check
+        assert_eq!(
+            result
+                .tokens
+                .iter()
+                .map(|t| t.to_string())
+                .collect::<Vec<String>>(),
+            vec!["IDENTIFIER foo null", "EOF  null"]
+        );
+        assert_eq!(
+            result.errors,
+            vec![super::Error::UnexpectedCharacter {
+                line: 1,
+                column: 1,
+                character: '@'
+            }]
+        );
+        assert_eq!(result.errors[0].message(), "Unexpected character: @");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_column_snippet_ok() -> Result<()> {
+        // Fixtures
+        let fx_content = "var a = 1;\nprint a @ 2;\nprint \"é\n";
+
+        // Init
+        let result = Scanner::from_source(fx_content).scan()?;
+        let errors = &result.errors;
+
+        // Check
+        assert_eq!(errors.len(), 2);
+        assert_eq!((errors[0].line(), errors[0].column()), (2, 9));
+        assert_eq!((errors[1].line(), errors[1].column()), (4, 1));
+        assert_eq!(
+            crate::snippet(fx_content, errors[0].line(), errors[0].column()),
+            "2 | print a @ 2;\n  |         ^"
+        );
+
+        Ok(())
+    }
 }
 
 // endregion: --- Tests
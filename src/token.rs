@@ -1,4 +1,4 @@
-use std::{fmt::Debug, hash::Hash};
+use std::{fmt::Debug, hash::Hash, rc::Rc};
 
 use crate::Value;
 
@@ -10,6 +10,9 @@ pub enum TokenType {
     RIGHT_PAREN,
     LEFT_BRACE,
     RIGHT_BRACE,
+    LEFT_BRACKET,
+    RIGHT_BRACKET,
+    COLON,
     COMMA,
     DOT,
     MINUS,
@@ -27,6 +30,7 @@ pub enum TokenType {
     GREATER_EQUAL,
     LESS,
     LESS_EQUAL,
+    QUESTION_QUESTION,
 
     // Literals.
     IDENTIFIER,
@@ -35,7 +39,9 @@ pub enum TokenType {
 
     // Keywords.
     AND,
+    BREAK,
     CLASS,
+    CONTINUE,
     ELSE,
     FALSE,
     FUN,
@@ -54,10 +60,63 @@ pub enum TokenType {
     EOF,
 }
 
+impl TokenType {
+    /// Every reserved word, in alphabetical order
+    pub const KEYWORDS: [TokenType; 18] = [
+        TokenType::AND,
+        TokenType::BREAK,
+        TokenType::CLASS,
+        TokenType::CONTINUE,
+        TokenType::ELSE,
+        TokenType::FALSE,
+        TokenType::FOR,
+        TokenType::FUN,
+        TokenType::IF,
+        TokenType::NIL,
+        TokenType::OR,
+        TokenType::PRINT,
+        TokenType::RETURN,
+        TokenType::SUPER,
+        TokenType::THIS,
+        TokenType::TRUE,
+        TokenType::VAR,
+        TokenType::WHILE,
+    ];
+
+    /// Source spelling of a keyword, `None` for other token types
+    pub fn keyword_str(&self) -> Option<&'static str> {
+        let keyword = match self {
+            TokenType::AND => "and",
+            TokenType::BREAK => "break",
+            TokenType::CLASS => "class",
+            TokenType::CONTINUE => "continue",
+            TokenType::ELSE => "else",
+            TokenType::FALSE => "false",
+            TokenType::FOR => "for",
+            TokenType::FUN => "fun",
+            TokenType::IF => "if",
+            TokenType::NIL => "nil",
+            TokenType::OR => "or",
+            TokenType::PRINT => "print",
+            TokenType::RETURN => "return",
+            TokenType::SUPER => "super",
+            TokenType::THIS => "this",
+            TokenType::TRUE => "true",
+            TokenType::VAR => "var",
+            TokenType::WHILE => "while",
+            _ => return None,
+        };
+
+        Some(keyword)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub token_type: TokenType,
-    pub lexeme: String,
+    /// Shared, so cloning tokens doesn't copy the text. Scanner interns lexemes,
+    /// equal ones point to the same allocation
+    pub lexeme: Rc<str>,
     pub literal: Option<Value>,
     pub line: usize,
 }
@@ -74,7 +133,7 @@ impl Hash for Token {
 impl Token {
     pub fn new(
         token_type: TokenType,
-        lexeme: impl Into<String>,
+        lexeme: impl Into<Rc<str>>,
         literal: Option<Value>,
         line: usize,
     ) -> Token {
@@ -89,11 +148,25 @@ impl Token {
     pub fn eof(line: usize) -> Self {
         Token {
             token_type: TokenType::EOF,
-            lexeme: String::new(),
+            lexeme: Rc::from(""),
             literal: None,
             line,
         }
     }
+
+    pub fn is_keyword(&self) -> bool {
+        self.token_type.keyword_str().is_some()
+    }
+
+    /// Literal formatted the same way as `print` shows it (`42` instead of `42.0`).
+    /// Returns `null` if token has no literal
+    pub fn display_value(&self) -> String {
+        if let Some(literal) = &self.literal {
+            literal.stringify()
+        } else {
+            String::from("null")
+        }
+    }
 }
 
 impl From<&Token> for Token {
@@ -102,6 +175,9 @@ impl From<&Token> for Token {
     }
 }
 
+/// Formats token as `tokenize` command prints it: `NUMBER 42 42.0`.
+/// Number literals always keep the fractional part, see [`Token::display_value`]
+/// for user facing form
 impl core::fmt::Display for Token {
     fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
         let literal = if let Some(literal) = &self.literal {
@@ -121,6 +197,9 @@ impl core::fmt::Display for TokenType {
             TokenType::RIGHT_PAREN => ")",
             TokenType::LEFT_BRACE => "{",
             TokenType::RIGHT_BRACE => "}",
+            TokenType::LEFT_BRACKET => "[",
+            TokenType::RIGHT_BRACKET => "]",
+            TokenType::COLON => ":",
             TokenType::COMMA => ",",
             TokenType::DOT => ".",
             TokenType::MINUS => "-",
@@ -136,18 +215,21 @@ impl core::fmt::Display for TokenType {
             TokenType::GREATER_EQUAL => ">=",
             TokenType::LESS => "<",
             TokenType::LESS_EQUAL => "<=",
+            TokenType::QUESTION_QUESTION => "??",
             TokenType::IDENTIFIER => "IDENTIFIER",
             TokenType::STRING => "STRING",
             TokenType::NUMBER => "NUMBER",
-            TokenType::AND => "&",
+            TokenType::AND => "and",
+            TokenType::BREAK => "BREAK",
             TokenType::CLASS => "CLASS",
+            TokenType::CONTINUE => "CONTINUE",
             TokenType::ELSE => "ELSE",
             TokenType::FALSE => "FALSE",
             TokenType::FUN => "FUN",
             TokenType::FOR => "FOR",
             TokenType::IF => "IF",
             TokenType::NIL => "NIL",
-            TokenType::OR => "OR",
+            TokenType::OR => "or",
             TokenType::PRINT => "PRINT",
             TokenType::RETURN => "RETURN",
             TokenType::SUPER => "SUPER",
@@ -161,3 +243,98 @@ impl core::fmt::Display for TokenType {
         write!(fmt, "{}", op)
     }
 }
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    type Error = Box<dyn std::error::Error>;
+    type Result<T> = core::result::Result<T, Error>; // For tests.
+
+    use super::*;
+
+    #[test]
+    fn test_token_display_tokenize_format_ok() -> Result<()> {
+        let integer = Token::new(TokenType::NUMBER, "42", Some(Value::Number(42.0)), 1);
+        let fraction = Token::new(TokenType::NUMBER, "4.20", Some(Value::Number(4.2)), 1);
+        let string = Token::new(
+            TokenType::STRING,
+            "\"foo\"",
+            Some(Value::String("foo".into())),
+            1,
+        );
+        let no_literal = Token::new(TokenType::PLUS, "+", None, 1);
+
+        assert_eq!(integer.to_string(), "NUMBER 42 42.0");
+        assert_eq!(fraction.to_string(), "NUMBER 4.20 4.2");
+        assert_eq!(string.to_string(), "STRING \"foo\" foo");
+        assert_eq!(no_literal.to_string(), "PLUS + null");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_token_type_display_logical_ok() -> Result<()> {
+        assert_eq!(TokenType::AND.to_string(), "and");
+        assert_eq!(TokenType::OR.to_string(), "or");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_token_display_escaped_string_ok() -> Result<()> {
+        // Lexeme is raw source with quotes and backslash, literal is the interpreted value
+        let escaped = Token::new(
+            TokenType::STRING,
+            r#""a\nb""#,
+            Some(Value::String("a\nb".into())),
+            1,
+        );
+
+        assert_eq!(escaped.to_string(), "STRING \"a\\nb\" a\nb");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_token_display_value_ok() -> Result<()> {
+        let integer = Token::new(TokenType::NUMBER, "42", Some(Value::Number(42.0)), 1);
+        let fraction = Token::new(TokenType::NUMBER, "4.20", Some(Value::Number(4.2)), 1);
+        let string = Token::new(
+            TokenType::STRING,
+            "\"foo\"",
+            Some(Value::String("foo".into())),
+            1,
+        );
+        let no_literal = Token::new(TokenType::PLUS, "+", None, 1);
+
+        assert_eq!(integer.display_value(), "42");
+        assert_eq!(fraction.display_value(), "4.2");
+        assert_eq!(string.display_value(), "foo");
+        assert_eq!(no_literal.display_value(), "null");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keyword_round_trip_ok() -> Result<()> {
+        for token_type in TokenType::KEYWORDS {
+            let keyword = token_type.keyword_str().ok_or("keyword without spelling")?;
+
+            let mut scanner = crate::Scanner::from_source(keyword);
+            scanner.scan_tokens()?;
+
+            let token = &scanner.tokens()[0];
+            assert_eq!(token.token_type, token_type);
+            assert_eq!(token.token_type.keyword_str(), Some(keyword));
+            assert!(token.is_keyword());
+        }
+
+        assert_eq!(TokenType::IDENTIFIER.keyword_str(), None);
+        assert!(!Token::new(TokenType::IDENTIFIER, "classy", None, 1).is_keyword());
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
@@ -1,6 +1,14 @@
-use crate::resolver::MutResolver;
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use indexmap::IndexMap;
+
+use crate::interpreter::Environment;
+use crate::resolver::{MutResolver, Resolver};
 use crate::{interpreter, resolver, value, MutInterpreter, TokenType, Value};
-use crate::{visitor::Acceptor, AstPrinter, Token};
+use crate::{visitor::Acceptor, AstPrinter, JsonPrinter, SourcePrinter, Token};
 
 use super::Stmt;
 
@@ -17,44 +25,160 @@ pub enum Expr {
         operator: Token,
         right: Box<Expr>,
     },
-    Variable(Token),
+    Variable {
+        name: Token,
+        /// Count of scopes between use and declaration, set by the resolver. `None` for globals
+        distance: Cell<Option<usize>>,
+    },
     Assign {
         name: Token,
         value: Box<Expr>,
+        /// Same as `distance` of [`Expr::Variable`]
+        distance: Cell<Option<usize>>,
     },
     Logical {
         left: Box<Expr>,
         operator: Token,
         right: Box<Expr>,
     },
+    Coalesce {
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
     Call {
         callee: Box<Expr>,
         paren: Token,
         arguments: Vec<Expr>,
     },
+    ListLiteral {
+        bracket: Token,
+        elements: Vec<Expr>,
+    },
+    MapLiteral {
+        brace: Token,
+        entries: Vec<(Expr, Expr)>,
+    },
+    Index {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+    },
+    SetIndex {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        value: Box<Expr>,
+    },
+    /// `object.name`. There are no classes, so properties are string keys of maps:
+    /// `m.name` reads `m["name"]`
+    Get {
+        object: Box<Expr>,
+        name: Token,
+    },
+    /// `object.name = value`, same as `object["name"] = value` on a map
+    Set {
+        object: Box<Expr>,
+        name: Token,
+        value: Box<Expr>,
+    },
+    /// `{ stmt; ... value }`, evaluates to `value` in own scope
+    Block(Vec<Stmt>, Box<Expr>),
 }
 
-impl Into<Stmt> for Expr {
-    fn into(self) -> Stmt {
-        Stmt::Expression(Box::new(self))
+impl From<Expr> for Stmt {
+    fn from(val: Expr) -> Self {
+        Stmt::Expression(Box::new(val))
     }
 }
 
 impl Expr {
+    /// Variable read, not resolved yet
+    pub fn variable(name: Token) -> Expr {
+        Expr::Variable {
+            name,
+            distance: Cell::default(),
+        }
+    }
+
     pub fn name(&self) -> Option<String> {
         match self {
-            Expr::Variable(token) => Some(token.lexeme.clone()),
-            Expr::Assign { name, .. } => Some(name.lexeme.clone()),
+            Expr::Variable { name, .. } | Expr::Assign { name, .. } => {
+                Some(name.lexeme.to_string())
+            }
             Expr::Binary { left, .. } => left.name(),
             Expr::Call { callee, .. } => callee.name(),
             _ => None,
         }
     }
-    fn parenthesize(visitor: &AstPrinter, name: impl Into<String>, exprs: &[&Box<Expr>]) -> String {
+    /// Line of the first token the expression keeps. Literals don't keep tokens
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Expr::Binary { operator, .. }
+            | Expr::Unary { operator, .. }
+            | Expr::Logical { operator, .. } => Some(operator.line),
+            Expr::Grouping(expr) => expr.line(),
+            Expr::Coalesce { left, right } => left.line().or_else(|| right.line()),
+            Expr::Literal(_) => None,
+            Expr::Variable { name, .. } | Expr::Assign { name, .. } => Some(name.line),
+            Expr::Call { paren, .. } => Some(paren.line),
+            Expr::ListLiteral { bracket, .. }
+            | Expr::Index { bracket, .. }
+            | Expr::SetIndex { bracket, .. } => Some(bracket.line),
+            Expr::MapLiteral { brace, .. } => Some(brace.line),
+            Expr::Get { name, .. } | Expr::Set { name, .. } => Some(name.line),
+            Expr::Block(stmts, value) => stmts
+                .iter()
+                .find_map(|stmt| stmt.line())
+                .or_else(|| value.line()),
+        }
+    }
+
+    /// Precedence of calls, indexing and property access. Targets of `a[i] = v` and
+    /// `a.b = v` are printed at it, as they are parsed as a call expression
+    const CALL_PRECEDENCE: u8 = 10;
+
+    /// Binding power of expression used to decide where parentheses are needed
+    /// when printing source. Higher binds tighter
+    fn precedence(&self, printer: &SourcePrinter) -> u8 {
+        match self {
+            Expr::Assign { .. } | Expr::SetIndex { .. } | Expr::Set { .. } => 1,
+            Expr::Coalesce { .. } => 2,
+            Expr::Logical { operator, .. } => match operator.token_type {
+                TokenType::OR => 3,
+                _ => 4,
+            },
+            Expr::Binary { operator, .. } => match operator.token_type {
+                TokenType::EQUAL_EQUAL | TokenType::BANG_EQUAL => 5,
+                TokenType::GREATER
+                | TokenType::GREATER_EQUAL
+                | TokenType::LESS
+                | TokenType::LESS_EQUAL => 6,
+                TokenType::MINUS | TokenType::PLUS => 7,
+                _ => 8,
+            },
+            Expr::Unary { .. } => 9,
+            Expr::Call { .. } | Expr::Index { .. } | Expr::Get { .. } => Self::CALL_PRECEDENCE,
+            Expr::Grouping(expr) if !printer.preserve_grouping() => expr.precedence(printer),
+            _ => 11,
+        }
+    }
+
+    /// Prints expression as source, wrapping it in parentheses if it binds weaker than `min`
+    fn source(&self, printer: &SourcePrinter, min: u8) -> String {
+        let result = self.accept(printer);
+
+        if self.precedence(printer) < min {
+            format!("({})", result)
+        } else {
+            result
+        }
+    }
+
+    fn parenthesize(visitor: &AstPrinter, name: &str, exprs: &[&Expr]) -> String {
         let mut result = String::new();
 
         result.push('(');
-        result.push_str(&name.into());
+        result.push_str(name);
 
         for expr in exprs {
             result.push(' ');
@@ -70,24 +194,29 @@ impl Expr {
 impl Acceptor<resolver::Result<()>, &MutResolver> for Expr {
     fn accept(&self, visitor: &MutResolver) -> resolver::Result<()> {
         match self {
-            Expr::Variable(token) => {
+            Expr::Variable { name, distance } => {
                 if let Some(scope) = visitor.borrow().scopes.last() {
-                    if let Some(value) = scope.get(&token.lexeme).cloned() {
-                        if value == false {
+                    if let Some(value) = scope.get(&name.lexeme).cloned() {
+                        if !value {
                             return Err(resolver::Error::LocalVarReadWhileInitialized(
-                                token.clone(),
+                                name.clone(),
                             ));
                         }
                     }
                 }
 
-                visitor.borrow_mut().resolve_local(token);
+                visitor.borrow_mut().mark_used(name);
+                visitor.borrow().resolve_local(name, distance);
 
                 Ok(())
             }
-            Expr::Assign { name, value } => {
+            Expr::Assign {
+                name,
+                value,
+                distance,
+            } => {
                 value.accept(visitor)?;
-                visitor.borrow_mut().resolve_local(name);
+                visitor.borrow().resolve_local(name, distance);
 
                 Ok(())
             }
@@ -107,7 +236,7 @@ impl Acceptor<resolver::Result<()>, &MutResolver> for Expr {
 
                 Ok(())
             }
-            Expr::Logical { left, right, .. } => {
+            Expr::Logical { left, right, .. } | Expr::Coalesce { left, right } => {
                 left.accept(visitor)?;
                 right.accept(visitor)?;
 
@@ -124,6 +253,56 @@ impl Acceptor<resolver::Result<()>, &MutResolver> for Expr {
 
                 Ok(())
             }
+            Expr::ListLiteral { elements, .. } => {
+                for element in elements {
+                    element.accept(visitor)?;
+                }
+
+                Ok(())
+            }
+            Expr::MapLiteral { entries, .. } => {
+                for (key, value) in entries {
+                    key.accept(visitor)?;
+                    value.accept(visitor)?;
+                }
+
+                Ok(())
+            }
+            Expr::Index { object, index, .. } => {
+                object.accept(visitor)?;
+                index.accept(visitor)?;
+
+                Ok(())
+            }
+            Expr::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                value.accept(visitor)?;
+                object.accept(visitor)?;
+                index.accept(visitor)?;
+
+                Ok(())
+            }
+            Expr::Get { object, .. } => object.accept(visitor),
+            Expr::Set { object, value, .. } => {
+                value.accept(visitor)?;
+                object.accept(visitor)?;
+
+                Ok(())
+            }
+            Expr::Block(stmts, value) => {
+                visitor.borrow_mut().begin_scope();
+
+                Resolver::resolve_block(visitor, stmts)?;
+                let result = value.accept(visitor);
+
+                visitor.borrow_mut().end_scope();
+
+                result
+            }
         }
     }
 }
@@ -139,7 +318,9 @@ impl Acceptor<interpreter::Result<Value>, &MutInterpreter> for Expr {
                 let left = left.accept(visitor)?;
                 let right = right.accept(visitor)?;
 
-                Ok(left.calculate(Some(&right), operator)?)
+                let mode = visitor.borrow().number_mode;
+
+                Ok(left.calculate_with(Some(&right), operator, mode)?)
             }
             Expr::Grouping(expr) => expr.accept(visitor),
             Expr::Literal(value) => {
@@ -152,20 +333,29 @@ impl Acceptor<interpreter::Result<Value>, &MutInterpreter> for Expr {
             Expr::Unary { operator, right } => {
                 let value = right.accept(visitor)?;
 
+                if operator.token_type == TokenType::BANG {
+                    return Ok(Value::Boolean(!visitor.borrow().is_truthy(&value)));
+                }
+
                 Ok(value.calculate(None, operator)?)
             }
-            Expr::Variable(name) => {
+            Expr::Variable { name, distance } => {
                 let interpreter = visitor.borrow();
 
-                Ok(interpreter.look_up_variable(name)?)
+                Ok(interpreter.look_up_variable(name, distance.get())?)
             }
-            Expr::Assign { name, value } => {
+            Expr::Assign {
+                name,
+                value,
+                distance,
+            } => {
                 let value = value.accept(visitor)?;
 
                 let interpreter = visitor.borrow();
 
-                if let Some(distance) = interpreter.locals.get(&name.lexeme).copied() {
-                    interpreter.environment.borrow_mut().assign_at(
+                if let Some(distance) = distance.get() {
+                    Environment::assign_at(
+                        &interpreter.environment,
                         distance,
                         name,
                         Some(value.clone()),
@@ -174,7 +364,7 @@ impl Acceptor<interpreter::Result<Value>, &MutInterpreter> for Expr {
                     interpreter
                         .globals
                         .borrow_mut()
-                        .assign(&name, Some(value.clone()))?;
+                        .assign(name, Some(value.clone()))?;
                 }
 
                 Ok(value)
@@ -185,19 +375,22 @@ impl Acceptor<interpreter::Result<Value>, &MutInterpreter> for Expr {
                 right,
             } => {
                 let left = left.accept(visitor)?;
+                let truthy = visitor.borrow().is_truthy(&left);
 
                 if operator.token_type == TokenType::OR {
-                    if left.is_truthy() {
-                        return Ok(left);
-                    }
-                } else {
-                    if !left.is_truthy() {
+                    if truthy {
                         return Ok(left);
                     }
+                } else if !truthy {
+                    return Ok(left);
                 }
 
                 right.accept(visitor)
             }
+            Expr::Coalesce { left, right } => match left.accept(visitor)? {
+                Value::Nil => right.accept(visitor),
+                left => Ok(left),
+            },
             Expr::Call {
                 callee,
                 arguments,
@@ -217,7 +410,7 @@ impl Acceptor<interpreter::Result<Value>, &MutInterpreter> for Expr {
                 }
 
                 let arity = callee.arity();
-                if arguments.len() != arity {
+                if !arity.accepts(arguments.len()) {
                     return Err(value::Error::InvalidCountOfArguments {
                         token: paren.clone(),
                         count: arguments.len(),
@@ -227,6 +420,75 @@ impl Acceptor<interpreter::Result<Value>, &MutInterpreter> for Expr {
 
                 Ok(callee.call(paren, visitor, &arguments)?)
             }
+            Expr::ListLiteral { elements, .. } => {
+                let elements = elements
+                    .iter()
+                    .map(|element| element.accept(visitor))
+                    .collect::<interpreter::Result<Vec<Value>>>()?;
+
+                Ok(Value::List(Rc::new(RefCell::new(elements))))
+            }
+            Expr::MapLiteral { brace, entries } => {
+                // Keys are checked by `as_key`, so none of them is mutable
+                #[allow(clippy::mutable_key_type)]
+                let mut map = IndexMap::new();
+
+                for (key, value) in entries {
+                    let key = key.accept(visitor)?;
+                    key.as_key(brace.clone())?;
+
+                    map.insert(key, value.accept(visitor)?);
+                }
+
+                Ok(Value::Map(Rc::new(RefCell::new(map))))
+            }
+            Expr::Index {
+                object,
+                bracket,
+                index,
+            } => {
+                let object = object.accept(visitor)?;
+                let index = index.accept(visitor)?;
+
+                Ok(object.get_index(&index, bracket)?)
+            }
+            Expr::SetIndex {
+                object,
+                bracket,
+                index,
+                value,
+            } => {
+                let object = object.accept(visitor)?;
+                let index = index.accept(visitor)?;
+                let value = value.accept(visitor)?;
+
+                object.set_index(&index, value.clone(), bracket)?;
+
+                Ok(value)
+            }
+            Expr::Get { object, name } => {
+                let object = object.accept(visitor)?;
+
+                Ok(object.get_property(name)?)
+            }
+            Expr::Set {
+                object,
+                name,
+                value,
+            } => {
+                let object = object.accept(visitor)?;
+                let value = value.accept(visitor)?;
+
+                object.set_property(name, value.clone())?;
+
+                Ok(value)
+            }
+            Expr::Block(stmts, value) => {
+                let mut interpreter = visitor.borrow_mut();
+
+                let env = Environment::new(Some(interpreter.environment.clone()));
+                interpreter.evaluate_block(stmts, value, Rc::new(RefCell::new(env)))
+            }
         }
     }
 }
@@ -238,28 +500,29 @@ impl Acceptor<String, &AstPrinter> for Expr {
                 left,
                 operator,
                 right,
-            } => Self::parenthesize(&visitor, &operator.lexeme, &[left, right]),
-            Expr::Grouping(expr) => Self::parenthesize(&visitor, "group", &[expr]),
+            } => Self::parenthesize(visitor, &operator.lexeme, &[left, right]),
+            Expr::Grouping(expr) => Self::parenthesize(visitor, "group", &[expr]),
             Expr::Literal(value) => match value {
                 None => panic!("Must not be None"),
                 Some(Value::String(s)) => s.clone(),
                 Some(Value::Number(n)) => format!("{:?}", n),
                 Some(Value::Boolean(b)) => b.to_string(),
                 Some(Value::Nil) => String::from("nil"),
-                Some(Value::Callable(c)) => c.stringify(),
+                Some(value) => value.stringify(),
             },
             Expr::Unary { operator, right } => {
-                Self::parenthesize(&visitor, &operator.lexeme, &[right])
+                Self::parenthesize(visitor, &operator.lexeme, &[right])
             }
-            Expr::Variable(name) => format!("{}", name.lexeme),
-            Expr::Assign { name, value } => {
+            Expr::Variable { name, .. } => name.lexeme.to_string(),
+            Expr::Assign { name, value, .. } => {
                 format!("{} = {}", name.lexeme, value.accept(visitor))
             }
             Expr::Logical {
                 left,
                 operator,
                 right,
-            } => Self::parenthesize(&visitor, &operator.lexeme, &[left, right]),
+            } => Self::parenthesize(visitor, &operator.lexeme, &[left, right]),
+            Expr::Coalesce { left, right } => Self::parenthesize(visitor, "??", &[left, right]),
             Expr::Call {
                 callee, arguments, ..
             } => {
@@ -271,6 +534,364 @@ impl Acceptor<String, &AstPrinter> for Expr {
 
                 format!("{}({})", callee.accept(visitor), arguments)
             }
+            Expr::ListLiteral { elements, .. } => {
+                let mut result = String::from("(list");
+
+                for element in elements {
+                    result.push(' ');
+                    result.push_str(&element.accept(visitor));
+                }
+
+                result.push(')');
+                result
+            }
+            Expr::MapLiteral { entries, .. } => {
+                let mut result = String::from("(map");
+
+                for (key, value) in entries {
+                    result.push(' ');
+                    result.push_str(&key.accept(visitor));
+                    result.push(' ');
+                    result.push_str(&value.accept(visitor));
+                }
+
+                result.push(')');
+                result
+            }
+            Expr::Index { object, index, .. } => {
+                Self::parenthesize(visitor, "index", &[object, index])
+            }
+            Expr::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => Self::parenthesize(visitor, "set-index", &[object, index, value]),
+            Expr::Get { object, name } => {
+                format!("(get {} {})", object.accept(visitor), name.lexeme)
+            }
+            Expr::Set {
+                object,
+                name,
+                value,
+            } => format!(
+                "(set {} {} {})",
+                object.accept(visitor),
+                name.lexeme,
+                value.accept(visitor)
+            ),
+            Expr::Block(stmts, value) => {
+                let mut result = String::from("(block");
+
+                for stmt in stmts {
+                    result.push(' ');
+                    result.push_str(&stmt.accept(visitor));
+                }
+
+                result.push(' ');
+                result.push_str(&value.accept(visitor));
+                result.push(')');
+                result
+            }
+        }
+    }
+}
+
+impl Acceptor<String, &SourcePrinter> for Expr {
+    fn accept(&self, visitor: &SourcePrinter) -> String {
+        let precedence = self.precedence(visitor);
+
+        match self {
+            Expr::Binary {
+                left,
+                operator,
+                right,
+            }
+            | Expr::Logical {
+                left,
+                operator,
+                right,
+            } => format!(
+                "{} {} {}",
+                left.source(visitor, precedence),
+                operator.lexeme,
+                right.source(visitor, precedence + 1)
+            ),
+            Expr::Coalesce { left, right } => format!(
+                "{} ?? {}",
+                left.source(visitor, precedence),
+                right.source(visitor, precedence + 1)
+            ),
+            Expr::Grouping(expr) => {
+                if visitor.preserve_grouping() {
+                    format!("({})", expr.accept(visitor))
+                } else {
+                    expr.accept(visitor)
+                }
+            }
+            Expr::Literal(value) => match value {
+                None => String::from("nil"),
+                Some(Value::String(s)) => format!("\"{}\"", s),
+                Some(value) => value.stringify(),
+            },
+            Expr::Unary { operator, right } => {
+                format!("{}{}", operator.lexeme, right.source(visitor, precedence))
+            }
+            Expr::Variable { name, .. } => name.lexeme.to_string(),
+            Expr::Assign { name, value, .. } => {
+                format!("{} = {}", name.lexeme, value.source(visitor, precedence))
+            }
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                let arguments = arguments
+                    .iter()
+                    .map(|arg| arg.accept(visitor))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+
+                format!("{}({})", callee.source(visitor, precedence), arguments)
+            }
+            Expr::ListLiteral { elements, .. } => {
+                let elements = elements
+                    .iter()
+                    .map(|element| element.accept(visitor))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+
+                format!("[{}]", elements)
+            }
+            Expr::MapLiteral { entries, .. } => {
+                let entries = entries
+                    .iter()
+                    .map(|(key, value)| {
+                        format!("{}: {}", key.accept(visitor), value.accept(visitor))
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ");
+
+                format!("{{{}}}", entries)
+            }
+            Expr::Index { object, index, .. } => {
+                format!(
+                    "{}[{}]",
+                    object.source(visitor, precedence),
+                    index.accept(visitor)
+                )
+            }
+            Expr::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => format!(
+                "{}[{}] = {}",
+                object.source(visitor, Self::CALL_PRECEDENCE),
+                index.accept(visitor),
+                value.source(visitor, precedence)
+            ),
+            Expr::Get { object, name } => {
+                format!("{}.{}", object.source(visitor, precedence), name.lexeme)
+            }
+            Expr::Set {
+                object,
+                name,
+                value,
+            } => format!(
+                "{}.{} = {}",
+                object.source(visitor, Self::CALL_PRECEDENCE),
+                name.lexeme,
+                value.source(visitor, precedence)
+            ),
+            Expr::Block(stmts, value) => {
+                let value = value
+                    .accept(visitor)
+                    .lines()
+                    .map(|line| format!("    {}\n", line))
+                    .collect::<String>();
+
+                format!("{{\n{}{}}}", Stmt::indented(visitor, stmts), value)
+            }
+        }
+    }
+}
+
+impl Acceptor<String, &JsonPrinter> for Expr {
+    #[allow(clippy::only_used_in_recursion)]
+    fn accept(&self, visitor: &JsonPrinter) -> String {
+        let line = |token: &Token| token.line.to_string();
+        let lexeme = |token: &Token| JsonPrinter::string(&token.lexeme);
+
+        match self {
+            Expr::Binary {
+                left,
+                operator: op,
+                right,
+            } => JsonPrinter::object(
+                "Binary",
+                &[
+                    ("operator", lexeme(op)),
+                    ("line", line(op)),
+                    ("left", left.accept(visitor)),
+                    ("right", right.accept(visitor)),
+                ],
+            ),
+            Expr::Logical {
+                left,
+                operator: op,
+                right,
+            } => JsonPrinter::object(
+                "Logical",
+                &[
+                    ("operator", lexeme(op)),
+                    ("line", line(op)),
+                    ("left", left.accept(visitor)),
+                    ("right", right.accept(visitor)),
+                ],
+            ),
+            Expr::Coalesce { left, right } => JsonPrinter::object(
+                "Coalesce",
+                &[
+                    ("left", left.accept(visitor)),
+                    ("right", right.accept(visitor)),
+                ],
+            ),
+            Expr::Grouping(expr) => {
+                JsonPrinter::object("Grouping", &[("expression", expr.accept(visitor))])
+            }
+            Expr::Literal(value) => {
+                let value = match value {
+                    None | Some(Value::Nil) => String::from("null"),
+                    Some(Value::String(s)) => JsonPrinter::string(s),
+                    Some(Value::Number(n)) => JsonPrinter::number(*n),
+                    Some(Value::Boolean(b)) => b.to_string(),
+                    Some(value) => JsonPrinter::string(&value.stringify()),
+                };
+
+                JsonPrinter::object("Literal", &[("value", value)])
+            }
+            Expr::Unary {
+                operator: op,
+                right,
+            } => JsonPrinter::object(
+                "Unary",
+                &[
+                    ("operator", lexeme(op)),
+                    ("line", line(op)),
+                    ("right", right.accept(visitor)),
+                ],
+            ),
+            Expr::Variable { name, .. } => {
+                JsonPrinter::object("Variable", &[("name", lexeme(name)), ("line", line(name))])
+            }
+            Expr::Assign { name, value, .. } => JsonPrinter::object(
+                "Assign",
+                &[
+                    ("name", lexeme(name)),
+                    ("line", line(name)),
+                    ("value", value.accept(visitor)),
+                ],
+            ),
+            Expr::Call {
+                callee,
+                paren,
+                arguments,
+            } => JsonPrinter::object(
+                "Call",
+                &[
+                    ("line", line(paren)),
+                    ("callee", callee.accept(visitor)),
+                    (
+                        "arguments",
+                        JsonPrinter::array(arguments.iter().map(|arg| arg.accept(visitor))),
+                    ),
+                ],
+            ),
+            Expr::ListLiteral { bracket, elements } => JsonPrinter::object(
+                "ListLiteral",
+                &[
+                    ("line", line(bracket)),
+                    (
+                        "elements",
+                        JsonPrinter::array(elements.iter().map(|e| e.accept(visitor))),
+                    ),
+                ],
+            ),
+            Expr::MapLiteral { brace, entries } => JsonPrinter::object(
+                "MapLiteral",
+                &[
+                    ("line", line(brace)),
+                    (
+                        "entries",
+                        JsonPrinter::array(entries.iter().map(|(key, value)| {
+                            JsonPrinter::object(
+                                "Entry",
+                                &[
+                                    ("key", key.accept(visitor)),
+                                    ("value", value.accept(visitor)),
+                                ],
+                            )
+                        })),
+                    ),
+                ],
+            ),
+            Expr::Index {
+                object,
+                bracket,
+                index,
+            } => JsonPrinter::object(
+                "Index",
+                &[
+                    ("line", line(bracket)),
+                    ("object", object.accept(visitor)),
+                    ("index", index.accept(visitor)),
+                ],
+            ),
+            Expr::SetIndex {
+                object,
+                bracket,
+                index,
+                value,
+            } => JsonPrinter::object(
+                "SetIndex",
+                &[
+                    ("line", line(bracket)),
+                    ("object", object.accept(visitor)),
+                    ("index", index.accept(visitor)),
+                    ("value", value.accept(visitor)),
+                ],
+            ),
+            Expr::Get { object, name } => JsonPrinter::object(
+                "Get",
+                &[
+                    ("name", lexeme(name)),
+                    ("line", line(name)),
+                    ("object", object.accept(visitor)),
+                ],
+            ),
+            Expr::Set {
+                object,
+                name,
+                value,
+            } => JsonPrinter::object(
+                "Set",
+                &[
+                    ("name", lexeme(name)),
+                    ("line", line(name)),
+                    ("object", object.accept(visitor)),
+                    ("value", value.accept(visitor)),
+                ],
+            ),
+            Expr::Block(stmts, value) => JsonPrinter::object(
+                "Block",
+                &[
+                    (
+                        "statements",
+                        JsonPrinter::array(stmts.iter().map(|stmt| stmt.accept(visitor))),
+                    ),
+                    ("value", value.accept(visitor)),
+                ],
+            ),
         }
     }
 }
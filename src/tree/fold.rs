@@ -0,0 +1,172 @@
+use crate::{TokenType, Value};
+
+use super::{Expr, Stmt};
+
+impl Expr {
+    /// Replaces `"a" + "b"` with `"ab"` anywhere in the expression, innermost first, so
+    /// `"a" + "b" + "c"` becomes one literal. Only pairs of string literals are folded,
+    /// `x + "a" + "b"` is kept as it is
+    pub fn fold_strings(&mut self) {
+        match self {
+            Expr::Literal(_) | Expr::Variable { .. } => {}
+            Expr::Grouping(expr)
+            | Expr::Unary { right: expr, .. }
+            | Expr::Assign { value: expr, .. }
+            | Expr::Get { object: expr, .. } => expr.fold_strings(),
+            Expr::Binary { left, right, .. }
+            | Expr::Logical { left, right, .. }
+            | Expr::Coalesce { left, right }
+            | Expr::Index {
+                object: left,
+                index: right,
+                ..
+            }
+            | Expr::Set {
+                object: left,
+                value: right,
+                ..
+            } => {
+                left.fold_strings();
+                right.fold_strings();
+            }
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                callee.fold_strings();
+                arguments.iter_mut().for_each(Expr::fold_strings);
+            }
+            Expr::ListLiteral { elements, .. } => elements.iter_mut().for_each(Expr::fold_strings),
+            Expr::MapLiteral { entries, .. } => {
+                for (key, value) in entries {
+                    key.fold_strings();
+                    value.fold_strings();
+                }
+            }
+            Expr::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                object.fold_strings();
+                index.fold_strings();
+                value.fold_strings();
+            }
+            Expr::Block(stmts, value) => {
+                stmts.iter_mut().for_each(Stmt::fold_strings);
+                value.fold_strings();
+            }
+        }
+
+        if let Expr::Binary {
+            left,
+            operator,
+            right,
+        } = self
+        {
+            if let (
+                TokenType::PLUS,
+                Expr::Literal(Some(Value::String(a))),
+                Expr::Literal(Some(Value::String(b))),
+            ) = (&operator.token_type, left.as_ref(), right.as_ref())
+            {
+                *self = Expr::Literal(Some(Value::String(format!("{}{}", a, b))));
+            }
+        }
+    }
+}
+
+impl Stmt {
+    /// See [`Expr::fold_strings`]
+    pub fn fold_strings(&mut self) {
+        match self {
+            Stmt::Print(expr) | Stmt::Expression(expr) => expr.fold_strings(),
+            Stmt::Var { initializer, .. } => initializer.iter_mut().for_each(|e| e.fold_strings()),
+            Stmt::Return { value, .. } => value.iter_mut().for_each(|e| e.fold_strings()),
+            Stmt::Block(stmts) | Stmt::Function { body: stmts, .. } => {
+                stmts.iter_mut().for_each(Stmt::fold_strings)
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                condition.fold_strings();
+                then_branch.fold_strings();
+                else_branch.iter_mut().for_each(|s| s.fold_strings());
+            }
+            Stmt::While {
+                condition,
+                body,
+                increment,
+                ..
+            } => {
+                condition.fold_strings();
+                body.fold_strings();
+                increment.iter_mut().for_each(|e| e.fold_strings());
+            }
+            Stmt::Break { .. } | Stmt::Continue { .. } => {}
+        }
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    type Error = Box<dyn std::error::Error>;
+    type Result<T> = core::result::Result<T, Error>; // For tests.
+
+    use super::*;
+    use crate::{AstPrinter, Parser, Scanner};
+
+    fn folded(source: &str) -> Result<Vec<Stmt>> {
+        let mut scanner = Scanner::from_source(source);
+        scanner.scan_tokens()?;
+
+        let mut stmts = Parser::new(scanner.tokens()).parse_stmt()?;
+        stmts.iter_mut().for_each(Stmt::fold_strings);
+
+        Ok(stmts)
+    }
+
+    #[test]
+    fn test_fold_strings_ok() -> Result<()> {
+        // -- Exec
+        let stmts = folded(r#"print "a" + "b" + "c";"#)?;
+
+        // -- Check
+        assert_eq!(
+            stmts,
+            vec![Stmt::Print(Box::new(Expr::Literal(Some(Value::from(
+                "abc"
+            )))))]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fold_strings_only_literals_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_cases = [
+            (r#"x + "a" + "b";"#, "(+ (+ x a) b)"),
+            (r#""a" + ("b" + "c");"#, "(+ a (group bc))"),
+            (r#"1 + 2;"#, "(+ 1.0 2.0)"),
+            (r#""a" + 1;"#, "(+ a 1.0)"),
+            (r#"fun f() { return "x" + "y"; }"#, "fn f() {return xy}"),
+        ];
+
+        for (source, expected) in fx_cases {
+            // -- Exec
+            let stmts = folded(source)?;
+
+            // -- Check
+            assert_eq!(AstPrinter.print(&stmts[0]).trim(), expected, "{}", source);
+        }
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
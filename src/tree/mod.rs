@@ -1,5 +1,8 @@
 mod expr;
+mod fold;
+mod node;
 mod stmt;
 
 pub use expr::Expr;
+pub use node::Node;
 pub use stmt::Stmt;
@@ -0,0 +1,179 @@
+use super::{Expr, Stmt};
+
+/// Any node of the syntax tree, used by read-only traversals
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Node<'a> {
+    Expr(&'a Expr),
+    Stmt(&'a Stmt),
+}
+
+impl<'a> Node<'a> {
+    /// Direct children in source order
+    pub fn children(self) -> Vec<Node<'a>> {
+        let mut children = Vec::new();
+
+        match self {
+            Node::Expr(expr) => match expr {
+                Expr::Literal(_) | Expr::Variable { .. } => {}
+                Expr::Grouping(expr) | Expr::Get { object: expr, .. } => {
+                    children.push(Node::Expr(expr))
+                }
+                Expr::Unary { right: value, .. } | Expr::Assign { value, .. } => {
+                    children.push(Node::Expr(value))
+                }
+                Expr::Binary { left, right, .. }
+                | Expr::Logical { left, right, .. }
+                | Expr::Coalesce { left, right } => {
+                    children.extend([Node::Expr(left), Node::Expr(right)])
+                }
+                Expr::Call {
+                    callee, arguments, ..
+                } => {
+                    children.push(Node::Expr(callee));
+                    children.extend(arguments.iter().map(Node::Expr));
+                }
+                Expr::ListLiteral { elements, .. } => {
+                    children.extend(elements.iter().map(Node::Expr))
+                }
+                Expr::MapLiteral { entries, .. } => {
+                    for (key, value) in entries {
+                        children.extend([Node::Expr(key), Node::Expr(value)]);
+                    }
+                }
+                Expr::Index { object, index, .. } => {
+                    children.extend([Node::Expr(object), Node::Expr(index)])
+                }
+                Expr::SetIndex {
+                    object,
+                    index,
+                    value,
+                    ..
+                } => children.extend([Node::Expr(object), Node::Expr(index), Node::Expr(value)]),
+                Expr::Set { object, value, .. } => {
+                    children.extend([Node::Expr(object), Node::Expr(value)])
+                }
+                Expr::Block(stmts, value) => {
+                    children.extend(stmts.iter().map(Node::Stmt));
+                    children.push(Node::Expr(value));
+                }
+            },
+            Node::Stmt(stmt) => match stmt {
+                Stmt::Print(expr) | Stmt::Expression(expr) => children.push(Node::Expr(expr)),
+                Stmt::Var { initializer, .. } => {
+                    children.extend(initializer.as_deref().map(Node::Expr))
+                }
+                Stmt::Return { value, .. } => children.extend(value.as_deref().map(Node::Expr)),
+                Stmt::Block(stmts) | Stmt::Function { body: stmts, .. } => {
+                    children.extend(stmts.iter().map(Node::Stmt))
+                }
+                Stmt::If {
+                    condition,
+                    then_branch,
+                    else_branch,
+                } => {
+                    children.push(Node::Expr(condition));
+                    children.push(Node::Stmt(then_branch));
+                    children.extend(else_branch.as_deref().map(Node::Stmt));
+                }
+                Stmt::While {
+                    condition,
+                    body,
+                    increment,
+                    ..
+                } => {
+                    children.push(Node::Expr(condition));
+                    children.push(Node::Stmt(body));
+                    children.extend(increment.as_deref().map(Node::Expr));
+                }
+                Stmt::Break { .. } | Stmt::Continue { .. } => {}
+            },
+        }
+
+        children
+    }
+
+    /// Calls `f` on this node and then on every descendant, depth first
+    pub fn walk(self, f: &mut impl FnMut(Node<'a>)) {
+        f(self);
+
+        for child in self.children() {
+            child.walk(f);
+        }
+    }
+}
+
+impl Expr {
+    /// See [`Node::walk`]
+    pub fn walk<'a>(&'a self, f: &mut impl FnMut(Node<'a>)) {
+        Node::Expr(self).walk(f)
+    }
+}
+
+impl Stmt {
+    /// See [`Node::walk`]
+    pub fn walk<'a>(&'a self, f: &mut impl FnMut(Node<'a>)) {
+        Node::Stmt(self).walk(f)
+    }
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    type Error = Box<dyn std::error::Error>;
+    type Result<T> = core::result::Result<T, Error>; // For tests.
+
+    use super::*;
+    use crate::{Parser, Scanner};
+
+    fn parse(source: &str) -> Result<Vec<Stmt>> {
+        let mut scanner = Scanner::from_source(source);
+        scanner.scan_tokens()?;
+
+        Ok(Parser::new(scanner.tokens()).parse_stmt()?)
+    }
+
+    /// Example pass: literals anywhere in the program
+    fn count_literals(stmts: &[Stmt]) -> usize {
+        let mut count = 0;
+
+        for stmt in stmts {
+            stmt.walk(&mut |node| {
+                if let Node::Expr(Expr::Literal(_)) = node {
+                    count += 1;
+                }
+            });
+        }
+
+        count
+    }
+
+    #[test]
+    fn test_walk_visits_every_node_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let stmts = parse(
+            r#"
+            var a = 1;
+            fun f(x) { return x + 2; }
+            if (a < 3) print f(a); else { a = [4, {"k": 5}]; }
+            while (false) a = { var b = 6; b };
+            "#,
+        )?;
+
+        // -- Exec
+        let mut nodes = 0;
+        for stmt in &stmts {
+            stmt.walk(&mut |_| nodes += 1);
+        }
+
+        // -- Check
+        // Six numbers, the `"k"` key and `false`
+        assert_eq!(count_literals(&stmts), 8);
+        // 2 for `var`, 5 for `fun`, 16 for `if`, 8 for `while`
+        assert_eq!(nodes, 31);
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
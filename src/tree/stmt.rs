@@ -3,7 +3,7 @@ use std::rc::Rc;
 
 use crate::interpreter::{self, Environment};
 use crate::resolver::{self, FunctionType, MutResolver, Resolver};
-use crate::{visitor::Acceptor, AstPrinter, Token};
+use crate::{visitor::Acceptor, AstPrinter, JsonPrinter, SourcePrinter, Token};
 use crate::{Callable, MutInterpreter, Value};
 
 use super::Expr;
@@ -25,6 +25,10 @@ pub enum Stmt {
     While {
         condition: Box<Expr>,
         body: Box<Stmt>,
+        /// Increment clause of desugared `for`. Runs after each iteration, even after `continue`
+        increment: Option<Box<Expr>>,
+        /// `name` of `name: while (...)`, target of `break name;` and `continue name;`
+        label: Option<Token>,
     },
     Function {
         name: Token,
@@ -35,6 +39,58 @@ pub enum Stmt {
         keyword: Token,
         value: Option<Box<Expr>>,
     },
+    Break {
+        keyword: Token,
+        label: Option<Token>,
+    },
+    Continue {
+        keyword: Token,
+        label: Option<Token>,
+    },
+}
+
+impl Stmt {
+    /// Best known source line of the statement
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Stmt::Print(expr) | Stmt::Expression(expr) => expr.line(),
+            Stmt::Var { name, .. } | Stmt::Function { name, .. } => Some(name.line),
+            Stmt::Block(stmts) => stmts.iter().find_map(|stmt| stmt.line()),
+            Stmt::If { condition, .. } | Stmt::While { condition, .. } => condition.line(),
+            Stmt::Return { keyword, .. }
+            | Stmt::Break { keyword, .. }
+            | Stmt::Continue { keyword, .. } => Some(keyword.line),
+        }
+    }
+
+    /// `keyword label` or just `keyword` for unlabeled `break` and `continue`
+    fn with_label(keyword: &str, label: &Option<Token>) -> String {
+        match label {
+            Some(label) => format!("{} {}", keyword, label.lexeme),
+            None => String::from(keyword),
+        }
+    }
+
+    /// `label: ` in front of a labeled loop
+    fn label_prefix(label: &Option<Token>) -> String {
+        label
+            .as_ref()
+            .map(|label| format!("{}: ", label.lexeme))
+            .unwrap_or_default()
+    }
+
+    /// Prints statements one per line, indenting each line by one level
+    pub(super) fn indented(visitor: &SourcePrinter, stmts: &[Stmt]) -> String {
+        stmts
+            .iter()
+            .map(|stmt| stmt.accept(visitor))
+            .flat_map(|s| {
+                s.lines()
+                    .map(|line| format!("    {}\n", line))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
 }
 
 impl Acceptor<resolver::Result<()>, &MutResolver> for Stmt {
@@ -43,45 +99,56 @@ impl Acceptor<resolver::Result<()>, &MutResolver> for Stmt {
             Stmt::Block(stmts) => {
                 visitor.borrow_mut().begin_scope();
 
-                Resolver::resolve_block(visitor, &stmts)?;
+                Resolver::resolve_block(visitor, stmts)?;
 
                 visitor.borrow_mut().end_scope();
 
                 Ok(())
             }
             Stmt::Var { name, initializer } => {
-                visitor.borrow_mut().declare(&name)?;
+                visitor.borrow_mut().declare(name)?;
+                visitor.borrow_mut().track_usage(name);
 
                 if let Some(initializer) = initializer {
                     initializer.accept(visitor)?;
                 }
 
-                visitor.borrow_mut().define(&name);
+                visitor.borrow_mut().define(name);
 
                 Ok(())
             }
             Stmt::Function { name, params, body } => {
-                visitor.borrow_mut().declare(&name)?;
-                visitor.borrow_mut().define(&name);
+                visitor.borrow_mut().declare(name)?;
+                visitor.borrow_mut().define(name);
 
                 let enclosing_function = visitor
                     .borrow_mut()
                     .replace_function(resolver::FunctionType::Function);
+                let enclosing_loops = visitor.borrow_mut().replace_loops(vec![]);
 
                 visitor.borrow_mut().begin_scope();
 
+                // Body is still resolved, so the scope is closed and errors in it are reported
+                let mut duplicate = None;
+
                 for param in params {
-                    visitor.borrow_mut().declare(&param)?;
-                    visitor.borrow_mut().define(&param);
+                    if visitor.borrow_mut().declare(param).is_err() {
+                        duplicate.get_or_insert_with(|| param.clone());
+                    }
+                    visitor.borrow_mut().define(param);
                 }
 
-                Resolver::resolve_block(visitor, &body)?;
+                Resolver::resolve_block(visitor, body)?;
 
                 visitor.borrow_mut().end_scope();
 
                 _ = visitor.borrow_mut().replace_function(enclosing_function);
+                _ = visitor.borrow_mut().replace_loops(enclosing_loops);
 
-                Ok(())
+                match duplicate {
+                    Some(param) => Err(resolver::Error::DuplicateParameter(param)),
+                    None => Ok(()),
+                }
             }
             Stmt::Expression(expr) => {
                 expr.accept(visitor)?;
@@ -117,11 +184,47 @@ impl Acceptor<resolver::Result<()>, &MutResolver> for Stmt {
 
                 Ok(())
             }
-            Stmt::While { condition, body } => {
+            Stmt::While {
+                condition,
+                body,
+                increment,
+                label,
+            } => {
                 condition.accept(visitor)?;
-                body.accept(visitor)?;
 
-                Ok(())
+                if let Some(increment) = increment {
+                    increment.accept(visitor)?;
+                }
+
+                visitor.borrow_mut().begin_loop(label.as_ref());
+                let result = body.accept(visitor);
+                visitor.borrow_mut().end_loop();
+
+                result
+            }
+            Stmt::Break { keyword, label } => {
+                if !visitor.borrow().in_loop() {
+                    return Err(resolver::Error::BreakOutsideLoop(keyword.clone()));
+                }
+
+                match label {
+                    Some(label) if !visitor.borrow().has_label(label) => {
+                        Err(resolver::Error::UnknownLabel(label.clone()))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            Stmt::Continue { keyword, label } => {
+                if !visitor.borrow().in_loop() {
+                    return Err(resolver::Error::ContinueOutsideLoop(keyword.clone()));
+                }
+
+                match label {
+                    Some(label) if !visitor.borrow().has_label(label) => {
+                        Err(resolver::Error::UnknownLabel(label.clone()))
+                    }
+                    _ => Ok(()),
+                }
             }
         }
     }
@@ -129,6 +232,14 @@ impl Acceptor<resolver::Result<()>, &MutResolver> for Stmt {
 
 impl Acceptor<interpreter::Result<()>, &MutInterpreter> for Stmt {
     fn accept(&self, visitor: &MutInterpreter) -> interpreter::Result<()> {
+        let step_hook = visitor.borrow().step_hook.clone();
+
+        if let Some(step_hook) = step_hook {
+            step_hook.call(self);
+        }
+
+        visitor.borrow().check_deadline()?;
+
         match self {
             Stmt::Expression(expr) => {
                 let _ = expr.accept(visitor)?;
@@ -136,7 +247,8 @@ impl Acceptor<interpreter::Result<()>, &MutInterpreter> for Stmt {
             }
             Stmt::Print(expr) => {
                 let value = expr.accept(visitor)?;
-                println!("{}", value.stringify());
+                let interpreter = visitor.borrow();
+                interpreter.write(&format!("{}\n", interpreter.stringify(&value)));
                 Ok(())
             }
             Stmt::Var { name, initializer } => {
@@ -151,7 +263,7 @@ impl Acceptor<interpreter::Result<()>, &MutInterpreter> for Stmt {
                 interpreter
                     .environment
                     .borrow_mut()
-                    .define(&name.lexeme, value);
+                    .define(name.lexeme.clone(), value);
 
                 Ok(())
             }
@@ -168,7 +280,7 @@ impl Acceptor<interpreter::Result<()>, &MutInterpreter> for Stmt {
             } => {
                 let value = condition.accept(visitor)?;
 
-                if value.is_truthy() {
+                if visitor.borrow().is_truthy(&value) {
                     then_branch.accept(visitor)
                 } else if let Some(else_branch) = else_branch {
                     else_branch.accept(visitor)
@@ -176,29 +288,61 @@ impl Acceptor<interpreter::Result<()>, &MutInterpreter> for Stmt {
                     Ok(())
                 }
             }
-            Stmt::While { condition, body } => {
-                while condition.accept(visitor)?.is_truthy() {
-                    body.accept(visitor)?
+            Stmt::While {
+                condition,
+                body,
+                increment,
+                label,
+            } => {
+                // Unlabeled `break` and `continue` target the innermost loop
+                let targets_this = |target: &Option<Rc<str>>| match target {
+                    Some(target) => label.as_ref().is_some_and(|l| &l.lexeme == target),
+                    None => true,
+                };
+
+                loop {
+                    let value = condition.accept(visitor)?;
+
+                    if !visitor.borrow().is_truthy(&value) {
+                        break;
+                    }
+
+                    match body.accept(visitor) {
+                        Ok(()) => {}
+                        Err(interpreter::Error::Continue(_, target)) if targets_this(&target) => {}
+                        Err(interpreter::Error::Break(_, target)) if targets_this(&target) => break,
+                        Err(e) => return Err(e),
+                    }
+
+                    if let Some(increment) = increment {
+                        increment.accept(visitor)?;
+                    }
                 }
 
                 Ok(())
             }
+            Stmt::Break { keyword, label } => {
+                let label = label.as_ref().map(|label| label.lexeme.clone());
+                Err(interpreter::Error::Break(keyword.clone(), label))
+            }
+            Stmt::Continue { keyword, label } => {
+                let label = label.as_ref().map(|label| label.lexeme.clone());
+                Err(interpreter::Error::Continue(keyword.clone(), label))
+            }
             Stmt::Function { name, params, body } => {
                 let interpreter = visitor.borrow();
 
                 let value = Value::Callable(Callable::Function {
-                    declaration: Box::new(Stmt::Function {
-                        name: name.clone(),
-                        params: params.clone(),
-                        body: body.clone(),
-                    }),
+                    name: Box::new(name.clone()),
+                    params: params.as_slice().into(),
+                    body: body.as_slice().into(),
                     closure: interpreter.environment.clone(),
                 });
 
                 interpreter
                     .environment
                     .borrow_mut()
-                    .define(&name.lexeme, Some(value));
+                    .define(name.lexeme.clone(), Some(value));
 
                 Ok(())
             }
@@ -242,7 +386,7 @@ impl Acceptor<String, &AstPrinter> for Stmt {
 
                 for stmt in stmts {
                     result.push_str(&stmt.accept(visitor));
-                    result.push_str("\n");
+                    result.push('\n');
                 }
 
                 result.push_str("}\n");
@@ -260,36 +404,65 @@ impl Acceptor<String, &AstPrinter> for Stmt {
                 result.push_str(&condition.accept(visitor));
                 result.push_str(") {");
                 result.push_str(&then_branch.accept(visitor));
-                result.push_str("}");
-
-                if let Some(else_branch) = else_branch {
-                    result.push_str(" else {");
-                    result.push_str(&else_branch.accept(visitor));
-                    result.push_str("}");
+                result.push('}');
+
+                match else_branch.as_deref() {
+                    // Chained `else if` is printed flat, not nested in braces
+                    Some(else_branch @ Stmt::If { .. }) => {
+                        result.push_str(" else ");
+                        result.push_str(&else_branch.accept(visitor));
+                    }
+                    Some(else_branch) => {
+                        result.push_str(" else {");
+                        result.push_str(&else_branch.accept(visitor));
+                        result.push('}');
+                    }
+                    None => {}
                 }
 
                 result
             }
-            Stmt::While { condition, body } => {
+            Stmt::While {
+                condition,
+                body,
+                increment,
+                label,
+            } => {
                 let mut result = String::new();
 
-                result.push_str("while ");
+                if let Some(label) = label {
+                    result.push_str(&label.lexeme);
+                    result.push_str(": ");
+                }
+
+                result.push_str("while (");
                 result.push_str(&condition.accept(visitor));
+                result.push_str(") {");
                 result.push_str(&body.accept(visitor));
+                result.push('}');
+
+                // Increment of a `for` loop, run after the body and on `continue`
+                if let Some(increment) = increment {
+                    result.push_str(" [");
+                    result.push_str(&increment.accept(visitor));
+                    result.push(']');
+                }
 
                 result
             }
+            Stmt::Break { label, .. } => Self::with_label("break", label),
+            Stmt::Continue { label, .. } => Self::with_label("continue", label),
             Stmt::Function { name, params, body } => {
                 let mut result = String::new();
 
                 result.push_str("fn ");
                 result.push_str(&name.lexeme);
 
-                result.push_str("(");
+                result.push('(');
                 result.push_str(
                     &params
                         .iter()
-                        .map(|p| p.lexeme.clone())
+                        .map(|p| p.lexeme.to_string())
                         .collect::<Vec<String>>()
                         .join(", "),
                 );
@@ -297,7 +470,7 @@ impl Acceptor<String, &AstPrinter> for Stmt {
                 for b in body {
                     result.push_str(&b.accept(visitor));
                 }
-                result.push_str("}");
+                result.push('}');
 
                 result
             }
@@ -316,3 +489,186 @@ impl Acceptor<String, &AstPrinter> for Stmt {
         }
     }
 }
+
+impl Acceptor<String, &SourcePrinter> for Stmt {
+    fn accept(&self, visitor: &SourcePrinter) -> String {
+        match self {
+            Stmt::Expression(expr) => format!("{};", expr.accept(visitor)),
+            Stmt::Print(expr) => format!("print {};", expr.accept(visitor)),
+            Stmt::Var { name, initializer } => {
+                if let Some(initializer) = initializer {
+                    format!("var {} = {};", name.lexeme, initializer.accept(visitor))
+                } else {
+                    format!("var {};", name.lexeme)
+                }
+            }
+            Stmt::Block(stmts) => format!("{{\n{}}}", Self::indented(visitor, stmts)),
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let mut result = format!(
+                    "if ({}) {}",
+                    condition.accept(visitor),
+                    then_branch.accept(visitor)
+                );
+
+                if let Some(else_branch) = else_branch {
+                    result.push_str(" else ");
+                    result.push_str(&else_branch.accept(visitor));
+                }
+
+                result
+            }
+            Stmt::While {
+                condition,
+                body,
+                increment: Some(increment),
+                label,
+            } => {
+                format!(
+                    "{}for (; {}; {}) {}",
+                    Self::label_prefix(label),
+                    condition.accept(visitor),
+                    increment.accept(visitor),
+                    body.accept(visitor)
+                )
+            }
+            Stmt::While {
+                condition,
+                body,
+                label,
+                ..
+            } => {
+                format!(
+                    "{}while ({}) {}",
+                    Self::label_prefix(label),
+                    condition.accept(visitor),
+                    body.accept(visitor)
+                )
+            }
+            Stmt::Function { name, params, body } => {
+                let params = params
+                    .iter()
+                    .map(|p| p.lexeme.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+
+                format!(
+                    "fun {}({}) {{\n{}}}",
+                    name.lexeme,
+                    params,
+                    Self::indented(visitor, body)
+                )
+            }
+            Stmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    format!("return {};", value.accept(visitor))
+                } else {
+                    String::from("return;")
+                }
+            }
+            Stmt::Break { label, .. } => format!("{};", Self::with_label("break", label)),
+            Stmt::Continue { label, .. } => format!("{};", Self::with_label("continue", label)),
+        }
+    }
+}
+
+impl Acceptor<String, &JsonPrinter> for Stmt {
+    fn accept(&self, visitor: &JsonPrinter) -> String {
+        let optional = |expr: &Option<Box<Expr>>| match expr {
+            Some(expr) => expr.accept(visitor),
+            None => String::from("null"),
+        };
+        let label_of = |label: &Option<Token>| match label {
+            Some(label) => JsonPrinter::string(&label.lexeme),
+            None => String::from("null"),
+        };
+        let stmts =
+            |stmts: &[Stmt]| JsonPrinter::array(stmts.iter().map(|stmt| stmt.accept(visitor)));
+
+        match self {
+            Stmt::Expression(expr) => {
+                JsonPrinter::object("Expression", &[("expression", expr.accept(visitor))])
+            }
+            Stmt::Print(expr) => {
+                JsonPrinter::object("Print", &[("expression", expr.accept(visitor))])
+            }
+            Stmt::Var { name, initializer } => JsonPrinter::object(
+                "Var",
+                &[
+                    ("name", JsonPrinter::string(&name.lexeme)),
+                    ("line", name.line.to_string()),
+                    ("initializer", optional(initializer)),
+                ],
+            ),
+            Stmt::Block(body) => JsonPrinter::object("Block", &[("statements", stmts(body))]),
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => JsonPrinter::object(
+                "If",
+                &[
+                    ("condition", condition.accept(visitor)),
+                    ("then", then_branch.accept(visitor)),
+                    (
+                        "else",
+                        match else_branch {
+                            Some(stmt) => stmt.accept(visitor),
+                            None => String::from("null"),
+                        },
+                    ),
+                ],
+            ),
+            Stmt::While {
+                condition,
+                body,
+                increment,
+                label,
+            } => JsonPrinter::object(
+                "While",
+                &[
+                    ("label", label_of(label)),
+                    ("condition", condition.accept(visitor)),
+                    ("body", body.accept(visitor)),
+                    ("increment", optional(increment)),
+                ],
+            ),
+            Stmt::Function { name, params, body } => JsonPrinter::object(
+                "Function",
+                &[
+                    ("name", JsonPrinter::string(&name.lexeme)),
+                    ("line", name.line.to_string()),
+                    (
+                        "params",
+                        JsonPrinter::array(params.iter().map(|p| JsonPrinter::string(&p.lexeme))),
+                    ),
+                    ("body", stmts(body)),
+                ],
+            ),
+            Stmt::Return { keyword, value } => JsonPrinter::object(
+                "Return",
+                &[
+                    ("line", keyword.line.to_string()),
+                    ("value", optional(value)),
+                ],
+            ),
+            Stmt::Break { keyword, label } => JsonPrinter::object(
+                "Break",
+                &[
+                    ("line", keyword.line.to_string()),
+                    ("label", label_of(label)),
+                ],
+            ),
+            Stmt::Continue { keyword, label } => JsonPrinter::object(
+                "Continue",
+                &[
+                    ("line", keyword.line.to_string()),
+                    ("label", label_of(label)),
+                ],
+            ),
+        }
+    }
+}
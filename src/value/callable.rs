@@ -9,26 +9,119 @@ use interpreter::Result;
 
 pub type CallableFn = fn(interpreter: &MutInterpreter, args: &[Value]) -> Result<Value>;
 
-#[derive(Debug, Clone, PartialEq)]
+/// How user functions are stringified
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FunctionStyle {
+    /// `<fn name>`, same as reference Lox
+    #[default]
+    Reference,
+    /// `<fn name(a, b)>`
+    Verbose,
+}
+
+/// Count of arguments a callable accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    Exact(usize),
+    /// Variadic, with required minimum
+    AtLeast(usize),
+}
+
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match self {
+            Arity::Exact(arity) => count == *arity,
+            Arity::AtLeast(min) => count >= *min,
+        }
+    }
+}
+
+impl From<usize> for Arity {
+    fn from(arity: usize) -> Self {
+        Arity::Exact(arity)
+    }
+}
+
+/// `2` or `at least 1`, as used in argument count errors
+impl core::fmt::Display for Arity {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        match self {
+            Arity::Exact(arity) => write!(fmt, "{}", arity),
+            Arity::AtLeast(min) => write!(fmt, "at least {}", min),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub enum Callable {
     BuiltIn {
         name: Box<Token>,
-        arity: usize,
+        arity: Arity,
         function: CallableFn,
     },
+    /// Parts of a `Stmt::Function` declaration, with the environment it was declared in
     Function {
-        declaration: Box<Stmt>,
+        name: Box<Token>,
+        params: Rc<[Token]>,
+        body: Rc<[Stmt]>,
         closure: MutEnv,
     },
 }
 
+impl PartialEq for Callable {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Callable::BuiltIn { name, arity, .. },
+                Callable::BuiltIn {
+                    name: other_name,
+                    arity: other_arity,
+                    ..
+                },
+            ) => name == other_name && arity == other_arity,
+            (
+                Callable::Function {
+                    name,
+                    params,
+                    body,
+                    closure,
+                },
+                Callable::Function {
+                    name: other_name,
+                    params: other_params,
+                    body: other_body,
+                    closure: other_closure,
+                },
+            ) => {
+                name == other_name
+                    && params == other_params
+                    && body == other_body
+                    && Rc::ptr_eq(closure, other_closure)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Compact as in verbose printing, e.g. `<fn add(a, b)>`. Body and closure are left
+/// out, so failing assertions on values stay readable
+impl core::fmt::Debug for Callable {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        write!(fmt, "{}", self.stringify_with(FunctionStyle::Verbose))
+    }
+}
+
 impl Callable {
-    pub fn arity(&self) -> usize {
+    /// Name the callable was declared or registered with
+    pub fn name(&self) -> &Token {
+        match self {
+            Callable::BuiltIn { name, .. } | Callable::Function { name, .. } => name,
+        }
+    }
+
+    pub fn arity(&self) -> Arity {
         match self {
-            Callable::Function { declaration, .. } => match declaration.as_ref() {
-                Stmt::Function { params, .. } => params.len(),
-                _ => panic!("not a function"),
-            },
+            Callable::Function { params, .. } => Arity::Exact(params.len()),
             Callable::BuiltIn { arity, .. } => *arity,
         }
     }
@@ -36,41 +129,148 @@ impl Callable {
     pub fn call(&self, interpreter: &MutInterpreter, args: &[Value]) -> Result<Value> {
         match self {
             Callable::Function {
-                declaration,
+                params,
+                body,
                 closure,
+                ..
             } => {
                 let mut interpreter = interpreter.borrow_mut();
 
                 let mut env = Environment::new(Some(closure.clone()));
 
-                let result = match declaration.as_ref() {
-                    Stmt::Function { params, body, .. } => {
-                        for (i, arg) in args.iter().enumerate() {
-                            env.define(&params.get(i).unwrap().lexeme, Some(arg.to_owned()));
-                        }
-
-                        match interpreter.execute_block(body, Rc::new(RefCell::new(env))) {
-                            Ok(_) => Ok(Value::Nil),
-                            Err(interpreter::Error::Return(value)) => Ok(value),
-                            Err(e) => Err(e),
-                        }
-                    }
-                    _ => panic!("not a function"),
-                };
-
-                result
+                for (param, arg) in params.iter().zip(args) {
+                    env.define(param.lexeme.clone(), Some(arg.to_owned()));
+                }
+
+                match interpreter.execute_block(body, Rc::new(RefCell::new(env))) {
+                    Ok(_) => Ok(Value::Nil),
+                    Err(interpreter::Error::Return(value)) => Ok(value),
+                    Err(e) => Err(e),
+                }
             }
             Callable::BuiltIn { function, .. } => function(interpreter, args),
         }
     }
 
+    /// User facing name, closure is never included
     pub fn stringify(&self) -> String {
+        self.stringify_with(FunctionStyle::default())
+    }
+
+    pub fn stringify_with(&self, style: FunctionStyle) -> String {
         match self {
-            Callable::Function { declaration, .. } => match declaration.as_ref() {
-                Stmt::Function { name, .. } => format!("<fn {}>", name.lexeme,),
-                _ => panic!("not a function"),
-            },
-            Callable::BuiltIn { name, .. } => format!("<native fn {}>", name),
+            Callable::Function { name, .. } if style == FunctionStyle::Reference => {
+                format!("<fn {}>", name.lexeme)
+            }
+            Callable::Function { name, params, .. } => {
+                let params = params
+                    .iter()
+                    .map(|param| &*param.lexeme)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                format!("<fn {}({})>", name.lexeme, params)
+            }
+            Callable::BuiltIn { name, .. } => format!("<native fn {}>", name.lexeme),
         }
     }
 }
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    type Error = Box<dyn std::error::Error>;
+    type Result<T> = core::result::Result<T, Error>; // For tests.
+
+    use crate::{interpreter::Environment, TokenType};
+
+    use super::*;
+
+    fn closure() -> MutEnv {
+        let mut env = Environment::default();
+        env.define("captured", Some(Value::Number(1.0)));
+
+        Rc::new(RefCell::new(env))
+    }
+
+    #[test]
+    fn test_callable_stringify_ok() -> Result<()> {
+        let function = Callable::Function {
+            name: Box::new(Token::new(TokenType::IDENTIFIER, "add", None, 1)),
+            params: Rc::from([]),
+            body: Rc::from([]),
+            closure: closure(),
+        };
+        let native = Callable::BuiltIn {
+            name: Box::new(Token::new(TokenType::IDENTIFIER, "clock", None, 0)),
+            arity: Arity::Exact(0),
+            function: |_, _| Ok(Value::Nil),
+        };
+
+        assert_eq!(function.stringify(), "<fn add>");
+        assert_eq!(native.stringify(), "<native fn clock>");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_callable_stringify_style_ok() -> Result<()> {
+        let param = |name: &str| Token::new(TokenType::IDENTIFIER, name, None, 1);
+
+        let function = Callable::Function {
+            name: Box::new(param("add")),
+            params: Rc::from([param("a"), param("b")]),
+            body: Rc::from([]),
+            closure: closure(),
+        };
+        let no_params = Callable::Function {
+            name: Box::new(param("now")),
+            params: Rc::from([]),
+            body: Rc::from([]),
+            closure: closure(),
+        };
+
+        assert_eq!(
+            function.stringify_with(FunctionStyle::Reference),
+            "<fn add>"
+        );
+        assert_eq!(
+            function.stringify_with(FunctionStyle::Verbose),
+            "<fn add(a, b)>"
+        );
+        assert_eq!(
+            no_params.stringify_with(FunctionStyle::Verbose),
+            "<fn now()>"
+        );
+        assert_eq!(function.stringify(), "<fn add>");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_callable_debug_compact_ok() -> Result<()> {
+        let param = |name: &str| Token::new(TokenType::IDENTIFIER, name, None, 1);
+
+        let function = Value::Callable(Callable::Function {
+            name: Box::new(param("add")),
+            params: Rc::from([param("a"), param("b")]),
+            body: Rc::from([Stmt::Print(Box::new(crate::Expr::Literal(Some(
+                Value::Nil,
+            ))))]),
+            closure: closure(),
+        });
+        let native = Value::Callable(Callable::BuiltIn {
+            name: Box::new(Token::new(TokenType::IDENTIFIER, "clock", None, 0)),
+            arity: Arity::Exact(0),
+            function: |_, _| Ok(Value::Nil),
+        });
+
+        assert_eq!(format!("{:?}", function), "Callable(<fn add(a, b)>)");
+        assert_eq!(format!("{:?}", native), "Callable(<native fn clock>)");
+
+        Ok(())
+    }
+}
+
+// endregion: --- Tests
@@ -1,4 +1,6 @@
-use crate::Token;
+use crate::{Token, TokenType};
+
+use super::Arity;
 
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -30,8 +32,59 @@ pub enum Error {
     InvalidCountOfArguments {
         token: Token,
         count: usize,
-        expected: usize,
+        expected: Arity,
+    },
+    /// Raised by a native function with its own message. `token` is the native's
+    /// name, its line is set to the call site when the native leaves it 0
+    Native {
+        token: Token,
+        message: String,
     },
+    /// Value of unexpected type passed to `TryFrom`, no source position is known
+    InvalidConversion {
+        expected: &'static str,
+        found: &'static str,
+    },
+}
+
+/// Constructors for errors of operators. They take operator token, so errors are
+/// reported at operator's line even if operands are on other lines
+impl Error {
+    pub fn invalid_operation(operator: Token, message: impl Into<String>) -> Self {
+        Error::InvalidOperation {
+            token: operator,
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_type(operator: Token, message: impl Into<String>) -> Self {
+        Error::InvalidType {
+            token: operator,
+            message: message.into(),
+        }
+    }
+
+    pub fn zero_division(operator: Token, message: impl Into<String>) -> Self {
+        Error::ZeroDivision {
+            token: operator,
+            message: message.into(),
+        }
+    }
+
+    pub fn must_be_number(operator: Token, message: impl Into<String>) -> Self {
+        Error::MustBeNumber {
+            token: operator,
+            message: message.into(),
+        }
+    }
+
+    /// Error of native `name`, reported at the line of the call
+    pub fn native(name: &str, message: impl Into<String>) -> Self {
+        Error::Native {
+            token: Token::new(TokenType::IDENTIFIER, name, None, 0),
+            message: message.into(),
+        }
+    }
 }
 
 // region:    --- Error Boilerplate
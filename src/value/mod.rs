@@ -1,33 +1,88 @@
 mod callable;
 mod error;
 
-pub use callable::{Callable, CallableFn};
+pub use callable::{Arity, Callable, CallableFn, FunctionStyle};
 pub use error::{Error, Result};
 
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
+
+use indexmap::IndexMap;
+
 use crate::{extensions::StringExt, interpreter, MutInterpreter, Token, TokenType};
 
-#[derive(Debug, Clone, PartialEq)]
+/// How numbers are compared by `==` and `!=`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberEquality {
+    /// IEEE 754 equality, same as reference Lox
+    #[default]
+    Exact,
+    /// Equal if difference is within absolute or relative epsilon, so `0.1 + 0.2 == 0.3`
+    Tolerant,
+}
+
+impl NumberEquality {
+    const ABSOLUTE_EPSILON: f64 = 1e-12;
+    const RELATIVE_EPSILON: f64 = 1e-9;
+
+    pub fn eq(&self, a: f64, b: f64) -> bool {
+        match self {
+            NumberEquality::Exact => a == b,
+            NumberEquality::Tolerant => {
+                let difference = (a - b).abs();
+
+                a == b
+                    || difference <= Self::ABSOLUTE_EPSILON
+                    || difference <= Self::RELATIVE_EPSILON * a.abs().max(b.abs())
+            }
+        }
+    }
+}
+
+/// What dividing by zero does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ZeroDivision {
+    /// Runtime error
+    #[default]
+    Error,
+    /// IEEE 754 result: `1 / 0` is infinity, `0 / 0` is `NaN`
+    Ieee,
+}
+
+/// Number semantics of operators, set on the interpreter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NumberMode {
+    pub equality: NumberEquality,
+    pub zero_division: ZeroDivision,
+}
+
+#[derive(Debug, Clone)]
 pub enum Value {
     String(String),
     Number(f64),
     Boolean(bool),
     Nil,
     Callable(Callable),
+    List(Rc<RefCell<Vec<Value>>>),
+    /// Iterated in insertion order. `PartialEq` compares entries ignoring the order,
+    /// Lox `==` compares maps by reference
+    Map(Rc<RefCell<IndexMap<Value, Value>>>),
 }
 
 impl Value {
-    pub fn arity(&self) -> usize {
+    pub fn arity(&self) -> Arity {
         match self {
             Value::Callable(callable) => callable.arity(),
-            _ => 0,
+            _ => Arity::Exact(0),
         }
     }
 
     pub fn is_callable(&self) -> bool {
-        match self {
-            Value::Callable(_) => true,
-            _ => false,
-        }
+        matches!(self, Value::Callable(_))
     }
 
     pub fn call(
@@ -37,32 +92,265 @@ impl Value {
         args: &[Value],
     ) -> std::result::Result<Value, interpreter::Error> {
         match self {
-            Value::Callable(callable) => callable.call(interpreter, args),
-            _ => {
-                return Err(Error::NotCallable {
-                    token: paren.clone(),
-                })?;
+            Value::Callable(callable) => callable
+                .call(interpreter, args)
+                .map_err(|error| error.with_call_line(paren.line)),
+            _ => Err(Error::NotCallable {
+                token: paren.clone(),
+            })?,
+        }
+    }
+
+    /// Same as [`Value::stringify`], but user functions are printed in given style
+    pub fn stringify_with(&self, style: FunctionStyle) -> String {
+        self.stringify_formatted(style, None)
+    }
+
+    /// Same as [`Value::stringify_with`], with numbers rounded to `precision` significant
+    /// digits. Applies to numbers and functions inside lists and maps too
+    pub fn stringify_formatted(&self, style: FunctionStyle, precision: Option<usize>) -> String {
+        match (self, precision) {
+            (Value::Number(n), Some(precision)) => {
+                Value::Number(Self::round_significant(*n, precision)).stringify()
+            }
+            (Value::Callable(callable), _) => callable.stringify_with(style),
+            (Value::List(_) | Value::Map(_), _) => {
+                self.stringify_container(&mut Vec::new(), style, precision)
             }
+            _ => self.stringify(),
         }
     }
 
+    /// Whole numbers are printed in plain decimal, never in scientific notation. Integers
+    /// are exact up to 2^53, past it the printed digits are of the nearest `f64`,
+    /// so `9007199254740993` prints as `9007199254740992`
     pub fn stringify(&self) -> String {
         match self {
             Value::String(s) => s.clone(),
+            Value::Number(n) if !n.is_finite() => Self::non_finite(*n).to_string(),
             Value::Number(n) => {
                 let mut s = n.to_string();
 
                 if s.ends_with(".0") {
                     s = s.substring(0, s.len() - 2);
                 }
-                return s;
+                s
             }
             Value::Boolean(b) => b.to_string(),
             Value::Nil => "nil".to_string(),
             Value::Callable(callable) => callable.stringify(),
+            Value::List(_) | Value::Map(_) => {
+                self.stringify_container(&mut Vec::new(), FunctionStyle::default(), None)
+            }
+        }
+    }
+
+    /// Value inside a list or a map. Strings are quoted there, so `["1"]` and `[1]`
+    /// print differently, while top-level strings stay bare
+    fn stringify_element(
+        &self,
+        visited: &mut Vec<*const ()>,
+        style: FunctionStyle,
+        precision: Option<usize>,
+    ) -> String {
+        match self {
+            Value::String(s) => format!("\"{}\"", s),
+            Value::List(_) | Value::Map(_) => self.stringify_container(visited, style, precision),
+            _ => self.stringify_formatted(style, precision),
+        }
+    }
+
+    /// Lists as `[1, "a"]`, maps as `{a: 1}` with keys shown bare. A container that
+    /// is already being printed by an outer call is shown as `[...]` or `{...}`
+    fn stringify_container(
+        &self,
+        visited: &mut Vec<*const ()>,
+        style: FunctionStyle,
+        precision: Option<usize>,
+    ) -> String {
+        let address = match self {
+            Value::List(list) => Rc::as_ptr(list).cast::<()>(),
+            Value::Map(map) => Rc::as_ptr(map).cast::<()>(),
+            _ => return self.stringify(),
+        };
+        let is_cycle = visited.contains(&address);
+
+        visited.push(address);
+
+        let result = match self {
+            Value::List(_) if is_cycle => String::from("[...]"),
+            Value::Map(_) if is_cycle => String::from("{...}"),
+            Value::List(list) => {
+                let elements = list
+                    .borrow()
+                    .iter()
+                    .map(|element| element.stringify_element(visited, style, precision))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                format!("[{}]", elements)
+            }
+            Value::Map(map) => {
+                let entries = map
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| {
+                        format!(
+                            "{}: {}",
+                            key.stringify_formatted(style, precision),
+                            value.stringify_element(visited, style, precision)
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                format!("{{{}}}", entries)
+            }
+            _ => self.stringify(),
+        };
+
+        visited.pop();
+
+        result
+    }
+
+    /// Values that can be map keys: strings, numbers, booleans and `nil`
+    pub fn is_hashable(&self) -> bool {
+        matches!(
+            self,
+            Value::String(_) | Value::Number(_) | Value::Boolean(_) | Value::Nil
+        )
+    }
+
+    /// Checks that value can be used as a map key
+    pub fn as_key(&self, token: impl Into<Token>) -> Result<&Value> {
+        if self.is_hashable() {
+            Ok(self)
+        } else {
+            Err(Error::InvalidType {
+                token: token.into(),
+                message: String::from("Map key must be a string, number, boolean or nil."),
+            })
+        }
+    }
+
+    /// Lox spelling of `NaN` and infinities, Rust prints them as `NaN` and `inf`
+    fn non_finite(n: f64) -> &'static str {
+        if n.is_nan() {
+            "nan"
+        } else if n.is_sign_positive() {
+            "Infinity"
+        } else {
+            "-Infinity"
+        }
+    }
+
+    /// Reads `self[index]`. Missing map keys read as `nil`, strings give one-char strings
+    pub fn get_index(&self, index: &Value, token: impl Into<Token>) -> Result<Value> {
+        let token: Token = token.into();
+
+        match (self, index) {
+            (Value::Map(map), key) => {
+                let key = key.as_key(token)?;
+
+                Ok(map.borrow().get(key).cloned().unwrap_or(Value::Nil))
+            }
+            (Value::List(list), index) => {
+                let i = Self::list_index(list.borrow().len(), index, token, "List")?;
+
+                Ok(list.borrow()[i].clone())
+            }
+            (Value::String(s), index) => {
+                let i = Self::list_index(s.chars().count(), index, token, "String")?;
+
+                Ok(Value::String(
+                    s.chars().nth(i).unwrap_or_default().to_string(),
+                ))
+            }
+            _ => Err(Error::InvalidOperation {
+                token,
+                message: String::from("Only lists, maps and strings can be indexed."),
+            }),
+        }
+    }
+
+    /// Writes `self[index] = value`. Map keys are inserted if missing
+    pub fn set_index(&self, index: &Value, value: Value, token: impl Into<Token>) -> Result<()> {
+        let token: Token = token.into();
+
+        match (self, index) {
+            (Value::Map(map), key) => {
+                let key = key.as_key(token)?;
+
+                map.borrow_mut().insert(key.clone(), value);
+
+                Ok(())
+            }
+            (Value::List(list), index) => {
+                let i = Self::list_index(list.borrow().len(), index, token, "List")?;
+
+                list.borrow_mut()[i] = value;
+
+                Ok(())
+            }
+            _ => Err(Error::InvalidOperation {
+                token,
+                message: String::from("Only lists and maps can be indexed."),
+            }),
+        }
+    }
+
+    /// Reads `self.name`. Properties are string keys of maps, missing ones are an error
+    pub fn get_property(&self, name: &Token) -> Result<Value> {
+        match self {
+            Value::Map(map) => map
+                .borrow()
+                .get(&Value::String(name.lexeme.to_string()))
+                .cloned()
+                .ok_or_else(|| Error::InvalidOperation {
+                    token: name.clone(),
+                    message: format!("Undefined property '{}'.", name.lexeme),
+                }),
+            _ => Err(Error::InvalidOperation {
+                token: name.clone(),
+                message: String::from("Only maps have properties."),
+            }),
+        }
+    }
+
+    /// Writes `self.name = value`, same as `self["name"] = value`
+    pub fn set_property(&self, name: &Token, value: Value) -> Result<()> {
+        match self {
+            Value::Map(map) => {
+                map.borrow_mut()
+                    .insert(Value::String(name.lexeme.to_string()), value);
+
+                Ok(())
+            }
+            _ => Err(Error::InvalidOperation {
+                token: name.clone(),
+                message: String::from("Only maps have properties."),
+            }),
+        }
+    }
+
+    fn list_index(len: usize, index: &Value, token: Token, kind: &str) -> Result<usize> {
+        match index {
+            Value::Number(n) if n.fract() == 0.0 && *n >= 0.0 && (*n as usize) < len => {
+                Ok(*n as usize)
+            }
+            Value::Number(_) => Err(Error::InvalidOperation {
+                token,
+                message: format!("{kind} index out of range."),
+            }),
+            _ => Err(Error::InvalidType {
+                token,
+                message: format!("{kind} index must be a number."),
+            }),
         }
     }
 
+    /// Lox truthiness: only `nil` and `false` are falsey
     pub fn is_truthy(&self) -> bool {
         match self {
             Value::Nil => false,
@@ -71,19 +359,96 @@ impl Value {
         }
     }
 
+    /// Truthiness familiar from other languages: `0`, `NaN` and `""` are falsey as well
+    pub fn is_truthy_loose(&self) -> bool {
+        match self {
+            Value::Number(n) => *n != 0.0 && !n.is_nan(),
+            Value::String(s) => !s.is_empty(),
+            _ => self.is_truthy(),
+        }
+    }
+
+    /// Rounds `n` to `digits` significant digits, at least one. Rounding is done in
+    /// decimal, so `0.1 + 0.2` becomes exactly `0.3`
+    pub fn round_significant(n: f64, digits: usize) -> f64 {
+        if !n.is_finite() {
+            return n;
+        }
+
+        format!("{:.*e}", digits.max(1) - 1, n).parse().unwrap_or(n)
+    }
+
+    /// Name of the value's type as shown in error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Number(_) => "number",
+            Value::Boolean(_) => "boolean",
+            Value::Nil => "nil",
+            Value::Callable(_) => "function",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+        }
+    }
+
+    /// Inner number, `None` for other variants. No conversion is done
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Inner string, `None` for other variants. No conversion is done
+    pub fn as_string(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
     pub fn is_equal(&self, other: &Value) -> bool {
+        self.is_equal_with(other, NumberEquality::default())
+    }
+
+    pub fn is_equal_with(&self, other: &Value, equality: NumberEquality) -> bool {
         match (self, other) {
             (Value::String(s1), Value::String(s2)) => s1 == s2,
-            (Value::Number(n1), Value::Number(n2)) => n1 == n2,
+            (Value::Number(n1), Value::Number(n2)) => equality.eq(*n1, *n2),
             (Value::Boolean(b1), Value::Boolean(b2)) => b1 == b2,
             (Value::Nil, Value::Nil) => true,
+            (Value::List(l1), Value::List(l2)) => Rc::ptr_eq(l1, l2),
+            (Value::Map(m1), Value::Map(m2)) => Rc::ptr_eq(m1, m2),
             _ => false,
         }
     }
 
+    /// Ordering used by `<`, `<=`, `>` and `>=`. Only numbers with numbers and strings
+    /// with strings are comparable, `None` otherwise (also for `NaN`).
+    ///
+    /// Strings are compared by unicode scalar values (same as by UTF-8 bytes),
+    /// so ordering is deterministic and doesn't depend on locale: `"B" < "a"`
+    pub fn cmp_values(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+
     /// `other` is optional. Needed only for uperations that can be done with one operand
-    /// like `!` or `-`
+    /// like `!`, `-` or `+`
     pub fn calculate(&self, other: Option<&Value>, token: impl Into<Token>) -> Result<Self> {
+        self.calculate_with(other, token, NumberMode::default())
+    }
+
+    /// Same as [`Value::calculate`], with number semantics of `mode`
+    pub fn calculate_with(
+        &self,
+        other: Option<&Value>,
+        token: impl Into<Token>,
+        mode: NumberMode,
+    ) -> Result<Self> {
         let token: Token = token.into();
         // TODO: Check error messages
 
@@ -92,51 +457,47 @@ impl Value {
             TokenType::MINUS => match (self, other) {
                 (Value::Number(a), Some(Value::Number(b))) => Ok(Value::Number(a - b)),
                 (Value::Number(a), None) => Ok(Value::Number(-a)),
-                (_, None) => Err(Error::MustBeNumber {
-                    token,
-                    message: String::from("Operand must be a number."),
-                }),
-                _ => Err(Error::InvalidType {
+                (_, None) => Err(Error::must_be_number(token, "Operand must be a number.")),
+                _ => Err(Error::invalid_type(
                     token,
-                    message: String::from("Operation must be done with numbers."),
-                }),
+                    "Operation must be done with numbers.",
+                )),
             },
             TokenType::PLUS => match (self, other) {
                 (Value::Number(a), Some(Value::Number(b))) => Ok(Value::Number(a + b)),
+                (Value::Number(a), None) => Ok(Value::Number(*a)),
+                (_, None) => Err(Error::must_be_number(token, "Operand must be a number.")),
                 (Value::String(a), Some(Value::String(b))) => {
                     Ok(Value::String(format!("{}{}", a, b)))
                 }
                 (Value::String(a), Some(Value::Number(b))) => {
                     Ok(Value::String(format!("{}{}", a, b)))
                 }
-                _ => Err(Error::InvalidType {
+                _ => Err(Error::invalid_type(
                     token,
-                    message: String::from("Operation must be done with numbers or strings."),
-                }),
+                    "Operation must be done with numbers or strings.",
+                )),
             },
             TokenType::SLASH => {
                 if let (Value::Number(a), Some(Value::Number(b))) = (self, other) {
-                    if *b == 0.0 {
-                        Err(Error::ZeroDivision {
-                            token,
-                            message: String::from("Cannot divide by zero."),
-                        })
+                    if *b == 0.0 && mode.zero_division == ZeroDivision::Error {
+                        Err(Error::zero_division(token, "Cannot divide by zero."))
                     } else {
                         Ok(Value::Number(a / b))
                     }
                 } else {
-                    Err(Error::InvalidType {
+                    Err(Error::invalid_type(
                         token,
-                        message: String::from("Operation must be done with numbers."),
-                    })
+                        "Operation must be done with numbers.",
+                    ))
                 }
             }
             TokenType::STAR => match (self, other) {
                 (Value::Number(a), Some(Value::Number(b))) => Ok(Value::Number(a * b)),
-                _ => Err(Error::InvalidType {
+                _ => Err(Error::invalid_type(
                     token,
-                    message: String::from("Operation must be done with numbers."),
-                }),
+                    "Operation must be done with numbers.",
+                )),
             },
 
             // - Bang
@@ -144,65 +505,111 @@ impl Value {
                 if other.is_none() {
                     Ok(Value::Boolean(!self.is_truthy()))
                 } else {
-                    Err(Error::InvalidOperation {
+                    Err(Error::invalid_operation(
                         token,
-                        message: String::from("Operation must be done with one operand."),
-                    })
+                        "Operation must be done with one operand.",
+                    ))
                 }
             }
 
             // - Comparisons
             TokenType::EQUAL_EQUAL => match (self, other) {
-                (left, Some(right)) => Ok(Value::Boolean(left.is_equal(right))),
-                _ => Err(Error::InvalidOperation {
+                (left, Some(right)) => Ok(Value::Boolean(left.is_equal_with(right, mode.equality))),
+                _ => Err(Error::invalid_operation(
                     token,
-                    message: String::from("Operation must be done with two operands."),
-                }),
+                    "Operation must be done with two operands.",
+                )),
             },
             TokenType::BANG_EQUAL => match (self, other) {
-                (left, Some(right)) => Ok(Value::Boolean(!left.is_equal(right))),
-                _ => Err(Error::InvalidOperation {
-                    token,
-                    message: String::from("Operation must be done with two operands."),
-                }),
-            },
-            TokenType::GREATER => match (self, other) {
-                (Value::Number(a), Some(Value::Number(b))) => Ok(Value::Boolean(a > b)),
-                (Value::String(a), Some(Value::String(b))) => Ok(Value::Boolean(a > b)),
-                _ => Err(Error::InvalidOperation {
-                    token,
-                    message: String::from("Operation must be done with two operands."),
-                }),
-            },
-            TokenType::GREATER_EQUAL => match (self, other) {
-                (Value::Number(a), Some(Value::Number(b))) => Ok(Value::Boolean(a >= b)),
-                (Value::String(a), Some(Value::String(b))) => Ok(Value::Boolean(a >= b)),
-                _ => Err(Error::InvalidOperation {
-                    token,
-                    message: String::from("Operation must be done with two operands."),
-                }),
-            },
-            TokenType::LESS => match (self, other) {
-                (Value::Number(a), Some(Value::Number(b))) => Ok(Value::Boolean(a < b)),
-                (Value::String(a), Some(Value::String(b))) => Ok(Value::Boolean(a < b)),
-                _ => Err(Error::InvalidOperation {
+                (left, Some(right)) => {
+                    Ok(Value::Boolean(!left.is_equal_with(right, mode.equality)))
+                }
+                _ => Err(Error::invalid_operation(
                     token,
-                    message: String::from("Operation must be done with two operands."),
-                }),
+                    "Operation must be done with two operands.",
+                )),
             },
-            TokenType::LESS_EQUAL => match (self, other) {
-                (Value::Number(a), Some(Value::Number(b))) => Ok(Value::Boolean(a <= b)),
-                (Value::String(a), Some(Value::String(b))) => Ok(Value::Boolean(a <= b)),
-                _ => Err(Error::InvalidOperation {
-                    token,
-                    message: String::from("Operation must be done with two operands."),
-                }),
+            TokenType::GREATER => self.compare(other, token, Ordering::is_gt),
+            TokenType::GREATER_EQUAL => self.compare(other, token, Ordering::is_ge),
+            TokenType::LESS => self.compare(other, token, Ordering::is_lt),
+            TokenType::LESS_EQUAL => self.compare(other, token, Ordering::is_le),
+
+            _ => Err(Error::invalid_operation(token, "Invalid operation.")),
+        }
+    }
+
+    /// Comparison through [`Value::cmp_values`]. Unordered numbers (`NaN`) compare as `false`
+    fn compare(
+        &self,
+        other: Option<&Value>,
+        token: Token,
+        test: fn(Ordering) -> bool,
+    ) -> Result<Self> {
+        match (self, other) {
+            (left, Some(right)) => match left.cmp_values(right) {
+                Some(ordering) => Ok(Value::Boolean(test(ordering))),
+                None if matches!((left, right), (Value::Number(_), Value::Number(_))) => {
+                    Ok(Value::Boolean(false))
+                }
+                None => Err(Self::comparison_error(left, other, token)),
             },
+            _ => Err(Self::comparison_error(self, other, token)),
+        }
+    }
 
-            _ => Err(Error::InvalidOperation {
+    /// `a < b < c` is `(a < b) < c`, so boolean on the left is most likely a chained comparison
+    fn comparison_error(left: &Value, right: Option<&Value>, token: Token) -> Error {
+        match (left, right) {
+            (Value::Boolean(_), Some(Value::Number(_))) => Error::invalid_type(
                 token,
-                message: String::from("Invalid operation."),
-            }),
+                "Cannot compare boolean with number; did you mean a chained comparison?",
+            ),
+            _ => Error::invalid_operation(token, "Operation must be done with two operands."),
+        }
+    }
+}
+
+/// Bits of number used for equality and hashing of map keys. All `NaN`s share
+/// one pattern and `-0.0` is the same as `0.0`, otherwise bits are compared as is,
+/// so `NaN` keys can be found again unlike with `==`
+fn number_bits(n: f64) -> u64 {
+    if n.is_nan() {
+        f64::NAN.to_bits()
+    } else if n == 0.0 {
+        0.0f64.to_bits()
+    } else {
+        n.to_bits()
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Number(a), Value::Number(b)) => number_bits(*a) == number_bits(*b),
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::Nil, Value::Nil) => true,
+            (Value::Callable(a), Value::Callable(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Map(a), Value::Map(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Value {}
+
+/// Only hashable variants (see [`Value::is_hashable`]) are hashed by content,
+/// the rest by variant only
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+
+        match self {
+            Value::String(s) => s.hash(state),
+            Value::Number(n) => number_bits(*n).hash(state),
+            Value::Boolean(b) => b.hash(state),
+            Value::Nil | Value::Callable(_) | Value::List(_) | Value::Map(_) => {}
         }
     }
 }
@@ -211,14 +618,81 @@ impl core::fmt::Display for Value {
     fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
         match self {
             Value::String(s) => write!(fmt, "{}", s),
+            Value::Number(n) if !n.is_finite() => write!(fmt, "{}", Self::non_finite(*n)),
+            // `{:?}` switches to scientific notation from 1e16
+            Value::Number(n) if n.fract() == 0.0 => write!(fmt, "{:.1}", n),
             Value::Number(n) => write!(fmt, "{:?}", n),
             Value::Boolean(b) => write!(fmt, "{}", b),
             Value::Nil => write!(fmt, "nil"),
             Value::Callable(c) => write!(fmt, "{}", c.stringify()),
+            Value::List(_) | Value::Map(_) => write!(fmt, "{}", self.stringify()),
         }
     }
 }
 
+// region:    --- Froms
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Number(n)
+    }
+}
+
+/// Lox numbers are floats, large integers lose precision
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Value::Number(n as f64)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Boolean(b)
+    }
+}
+
+impl TryFrom<Value> for f64 {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self> {
+        match value {
+            Value::Number(n) => Ok(n),
+            _ => Err(Error::InvalidConversion {
+                expected: "number",
+                found: value.type_name(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self> {
+        match value {
+            Value::String(s) => Ok(s),
+            _ => Err(Error::InvalidConversion {
+                expected: "string",
+                found: value.type_name(),
+            }),
+        }
+    }
+}
+
+// endregion: --- Froms
+
 // region:    --- Tests
 
 #[cfg(test)]
@@ -232,6 +706,48 @@ mod tests {
         Token::new(token_type.clone(), token_type.to_string(), None, 1)
     }
 
+    #[test]
+    fn test_value_from_ok() -> Result<()> {
+        assert_eq!(Value::from(6.5), Value::Number(6.5));
+        assert_eq!(Value::from(6i64), Value::Number(6.0));
+        assert_eq!(Value::from("six"), Value::String("six".to_string()));
+        assert_eq!(
+            Value::from(String::from("six")),
+            Value::String("six".to_string())
+        );
+        assert_eq!(Value::from(true), Value::Boolean(true));
+
+        let n: f64 = Value::from(6.5).try_into()?;
+        let s: String = Value::from("six").try_into()?;
+        assert_eq!(n, 6.5);
+        assert_eq!(s, "six");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_value_try_into_mismatch_err() -> Result<()> {
+        let number: core::result::Result<f64, _> = Value::from("six").try_into();
+        let string: core::result::Result<String, _> = Value::Nil.try_into();
+
+        assert!(matches!(
+            number,
+            Err(super::Error::InvalidConversion {
+                expected: "number",
+                found: "string"
+            })
+        ));
+        assert!(matches!(
+            string,
+            Err(super::Error::InvalidConversion {
+                expected: "string",
+                found: "nil"
+            })
+        ));
+
+        Ok(())
+    }
+
     #[test]
     /// Tests what prints to console by display
     fn test_value_display_ok() -> Result<()> {
@@ -273,6 +789,47 @@ mod tests {
     }
 
     #[test]
+    fn test_value_large_integers_ok() -> Result<()> {
+        let trillion = Value::Number(1000000000000.0);
+        let past_exact = Value::Number(9007199254740994.0);
+        let huge = Value::Number(1e21);
+
+        assert_eq!("1000000000000", trillion.stringify());
+        assert_eq!("1000000000000.0", format!("{}", trillion));
+
+        assert_eq!("9007199254740994", past_exact.stringify());
+        assert_eq!("9007199254740994.0", format!("{}", past_exact));
+        // 2^53 + 1 has no exact `f64`, it is rounded to 2^53
+        assert_eq!(
+            "9007199254740992",
+            Value::Number("9007199254740993".parse()?).stringify()
+        );
+
+        assert_eq!("1000000000000000000000", huge.stringify());
+        assert_eq!("1000000000000000000000.0", format!("{}", huge));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_value_non_finite_ok() -> Result<()> {
+        let nan = Value::Number(f64::NAN);
+        let infinity = Value::Number(f64::INFINITY);
+        let neg_infinity = Value::Number(f64::NEG_INFINITY);
+
+        assert_eq!("nan", nan.stringify());
+        assert_eq!("Infinity", infinity.stringify());
+        assert_eq!("-Infinity", neg_infinity.stringify());
+
+        assert_eq!("nan", format!("{}", nan));
+        assert_eq!("Infinity", format!("{}", infinity));
+        assert_eq!("-Infinity", format!("{}", neg_infinity));
+
+        Ok(())
+    }
+
+    #[test]
+    /// Lox treats only `nil` and `false` as falsey, so `0` and `""` are truthy
     fn test_value_truthy_ok() -> Result<()> {
         assert!(!Value::Nil.is_truthy());
         assert!(Value::Boolean(true).is_truthy());
@@ -283,6 +840,37 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    /// Loose mode additionally treats `0`, `NaN` and `""` as falsey
+    fn test_value_truthy_loose_ok() -> Result<()> {
+        assert!(!Value::Nil.is_truthy_loose());
+        assert!(Value::Boolean(true).is_truthy_loose());
+        assert!(!Value::Boolean(false).is_truthy_loose());
+        assert!(!Value::Number(0.0).is_truthy_loose());
+        assert!(!Value::Number(f64::NAN).is_truthy_loose());
+        assert!(Value::Number(-1.0).is_truthy_loose());
+        assert!(!Value::String(String::new()).is_truthy_loose());
+        assert!(Value::String("0".to_string()).is_truthy_loose());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_value_coercion_helpers_ok() -> Result<()> {
+        assert_eq!(Value::Number(6.0).as_number(), Some(6.0));
+        assert_eq!(Value::String("6".to_string()).as_number(), None);
+        assert_eq!(Value::Nil.as_number(), None);
+
+        assert_eq!(
+            Value::String("hello".to_string()).as_string(),
+            Some("hello")
+        );
+        assert_eq!(Value::Number(6.0).as_string(), None);
+        assert_eq!(Value::Boolean(true).as_string(), None);
+
+        Ok(())
+    }
+
     #[test]
     fn test_value_operation_negation_ok() -> Result<()> {
         let negate = |left: &Value, right: Option<&Value>| {
@@ -536,6 +1124,250 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_value_cross_type_equality_false_ok() -> Result<()> {
+        let values = [
+            Value::Number(1.0),
+            Value::String("1".to_string()),
+            Value::Boolean(true),
+            Value::Nil,
+            Value::List(Rc::new(RefCell::new(vec![]))),
+            Value::Map(Rc::new(RefCell::new(IndexMap::new()))),
+        ];
+
+        for (i, left) in values.iter().enumerate() {
+            for (j, right) in values.iter().enumerate() {
+                let equal = left.calculate(Some(right), create_token(TokenType::EQUAL_EQUAL))?;
+                let not_equal = left.calculate(Some(right), create_token(TokenType::BANG_EQUAL))?;
+
+                assert_eq!(equal, Value::Boolean(i == j), "{:?} == {:?}", left, right);
+                assert_eq!(
+                    not_equal,
+                    Value::Boolean(i != j),
+                    "{:?} != {:?}",
+                    left,
+                    right
+                );
+            }
+        }
+
+        // Falsey and empty values are still different types
+        assert!(!Value::Nil.is_equal(&Value::Boolean(false)));
+        assert!(!Value::Number(0.0).is_equal(&Value::Boolean(false)));
+        assert!(!Value::String(String::new()).is_equal(&Value::Nil));
+        assert!(!Value::Number(0.0).is_equal(&Value::String("0".to_string())));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_value_stringify_containers_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let list = |elements: Vec<Value>| Value::List(Rc::new(RefCell::new(elements)));
+        let map = |entries: Vec<(&str, Value)>| {
+            Value::Map(Rc::new(RefCell::new(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (Value::from(key), value))
+                    .collect(),
+            )))
+        };
+
+        let nested = list(vec![
+            Value::Number(1.0),
+            list(vec![Value::Number(2.0), Value::from("a")]),
+            list(vec![]),
+            map(vec![("k", Value::from("v")), ("n", Value::Nil)]),
+        ]);
+
+        // -- Exec & Check
+        assert_eq!(nested.stringify(), r#"[1, [2, "a"], [], {k: "v", n: nil}]"#);
+        assert_eq!(nested.to_string(), nested.stringify());
+        assert_eq!(map(vec![]).stringify(), "{}");
+        assert_eq!(list(vec![]).stringify(), "[]");
+        assert_eq!(Value::from("a").stringify(), "a");
+
+        // Self-referential list is cut where it repeats, siblings are printed in full
+        let inner = list(vec![Value::Number(3.0)]);
+        let cyclic = list(vec![inner.clone(), inner]);
+        if let Value::List(elements) = &cyclic {
+            elements.borrow_mut().push(cyclic.clone());
+        }
+        assert_eq!(cyclic.stringify(), "[[3], [3], [...]]");
+
+        // Breaks the cycle, so the list is freed
+        if let Value::List(elements) = &cyclic {
+            elements.borrow_mut().clear();
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_value_zero_division_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let ieee = NumberMode {
+            zero_division: ZeroDivision::Ieee,
+            ..Default::default()
+        };
+        let divide = |a: f64, mode| {
+            Value::Number(a).calculate_with(
+                Some(&Value::Number(0.0)),
+                create_token(TokenType::SLASH),
+                mode,
+            )
+        };
+
+        // -- Exec & Check
+        assert!(matches!(
+            divide(1.0, NumberMode::default()),
+            Err(super::Error::ZeroDivision { .. })
+        ));
+        assert!(matches!(
+            divide(0.0, NumberMode::default()),
+            Err(super::Error::ZeroDivision { .. })
+        ));
+
+        assert_eq!(divide(1.0, ieee)?, Value::Number(f64::INFINITY));
+        assert_eq!(divide(-1.0, ieee)?, Value::Number(f64::NEG_INFINITY));
+        assert!(divide(0.0, ieee)?.as_number().is_some_and(f64::is_nan));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_value_number_equality_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let sum = Value::Number(0.1)
+            .calculate(Some(&Value::Number(0.2)), create_token(TokenType::PLUS))?;
+        let expected = Value::Number(0.3);
+
+        // -- Exec & Check
+        for (equality, equal) in [
+            (NumberEquality::Exact, false),
+            (NumberEquality::Tolerant, true),
+        ] {
+            let mode = NumberMode {
+                equality,
+                ..Default::default()
+            };
+
+            assert_eq!(
+                sum.calculate_with(Some(&expected), create_token(TokenType::EQUAL_EQUAL), mode)?,
+                Value::Boolean(equal),
+                "{:?}",
+                equality
+            );
+            assert_eq!(
+                sum.calculate_with(Some(&expected), create_token(TokenType::BANG_EQUAL), mode)?,
+                Value::Boolean(!equal),
+                "{:?}",
+                equality
+            );
+        }
+
+        // Tolerance is relative for big numbers, NaN is never equal
+        let tolerant = NumberEquality::Tolerant;
+        assert!(tolerant.eq(1e20, 1e20 + 1e5));
+        assert!(!tolerant.eq(1.0, 1.001));
+        assert!(!tolerant.eq(f64::NAN, f64::NAN));
+        assert!(tolerant.eq(f64::INFINITY, f64::INFINITY));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_value_hash_key_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        #[allow(clippy::mutable_key_type)]
+        let mut map = std::collections::HashMap::new();
+        map.insert(Value::Number(0.0), "zero");
+        map.insert(Value::Number(f64::NAN), "nan");
+        map.insert(Value::Boolean(true), "true");
+        map.insert(Value::String("0".into()), "string");
+
+        // -- Check
+        // -0.0 is the same key as 0.0, every NaN is the same key
+        assert_eq!(map.get(&Value::Number(-0.0)), Some(&"zero"));
+        assert_eq!(map.get(&Value::Number(-f64::NAN)), Some(&"nan"));
+        assert_eq!(
+            map.get(&Value::Number(f64::from_bits(0x7ff8_0000_0000_0001))),
+            Some(&"nan")
+        );
+        assert_eq!(map.get(&Value::Boolean(true)), Some(&"true"));
+        assert_eq!(map.get(&Value::Boolean(false)), None);
+        assert_eq!(map.get(&Value::String("0".into())), Some(&"string"));
+        assert_eq!(map.len(), 4);
+
+        // Lox `==` still follows IEEE 754
+        assert!(!Value::Number(f64::NAN).is_equal(&Value::Number(f64::NAN)));
+        assert!(Value::Number(-0.0).is_equal(&Value::Number(0.0)));
+
+        let token = create_token(TokenType::LEFT_BRACKET);
+        assert!(Value::Nil.as_key(token.clone()).is_ok());
+        assert!(Value::List(Rc::new(RefCell::new(vec![])))
+            .as_key(token)
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_value_cmp_values_ok() -> Result<()> {
+        let string = |s: &str| Value::String(s.to_string());
+
+        assert_eq!(string("a").cmp_values(&string("b")), Some(Ordering::Less));
+        assert_eq!(string("B").cmp_values(&string("a")), Some(Ordering::Less));
+        assert_eq!(
+            string("ab").cmp_values(&string("a")),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            string("é").cmp_values(&string("z")),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(string("a").cmp_values(&string("a")), Some(Ordering::Equal));
+        assert_eq!(
+            Value::Number(1.0).cmp_values(&Value::Number(2.0)),
+            Some(Ordering::Less)
+        );
+
+        // Not comparable
+        assert_eq!(Value::Number(1.0).cmp_values(&string("1")), None);
+        assert_eq!(
+            Value::Number(f64::NAN).cmp_values(&Value::Number(1.0)),
+            None
+        );
+        assert_eq!(Value::Nil.cmp_values(&Value::Nil), None);
+        assert_eq!(
+            Value::Boolean(true).cmp_values(&Value::Boolean(false)),
+            None
+        );
+
+        // Same ordering is used by operators
+        assert_eq!(
+            string("B").calculate(Some(&string("a")), create_token(TokenType::LESS))?,
+            Value::Boolean(true)
+        );
+        assert!(Value::Number(1.0)
+            .calculate(Some(&string("1")), create_token(TokenType::LESS))
+            .is_err());
+        for token_type in [
+            TokenType::GREATER,
+            TokenType::GREATER_EQUAL,
+            TokenType::LESS,
+            TokenType::LESS_EQUAL,
+        ] {
+            assert_eq!(
+                Value::Number(f64::NAN)
+                    .calculate(Some(&Value::Number(1.0)), create_token(token_type))?,
+                Value::Boolean(false)
+            );
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_value_operation_bang_ok() -> Result<()> {
         let bang = |left: &Value, right: Option<&Value>| {
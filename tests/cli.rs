@@ -0,0 +1,371 @@
+type Error = Box<dyn std::error::Error>;
+type Result<T> = core::result::Result<T, Error>; // For tests.
+
+use std::{
+    fs,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+fn write_program(name: &str, source: &str) -> Result<PathBuf> {
+    let path = std::env::temp_dir().join(format!("lox-cli-{}-{}.lox", std::process::id(), name));
+
+    fs::write(&path, source)?;
+
+    Ok(path)
+}
+
+fn execute(command: &str, path: &PathBuf) -> Result<Output> {
+    execute_with(command, path, &[])
+}
+
+fn execute_with(command: &str, path: &PathBuf, flags: &[&str]) -> Result<Output> {
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg(command)
+        .arg(path)
+        .args(flags)
+        .env("RUST_LOG", "off")
+        .output()?;
+
+    Ok(output)
+}
+
+#[test]
+fn test_benchmark_stdout_unchanged_ok() -> Result<()> {
+    // -- Setup & Fixtures
+    let path = write_program(
+        "benchmark",
+        "var a = 1;\nfor (var i = 0; i < 3; i = i + 1) {\n  print a + i;\n}\n",
+    )?;
+
+    // -- Exec
+    let output = execute("benchmark", &path)?;
+    fs::remove_file(&path)?;
+
+    // -- Check
+    let stdout = String::from_utf8(output.stdout)?;
+    let stderr = String::from_utf8(output.stderr)?;
+
+    assert!(output.status.success());
+    assert_eq!(stdout, "1\n2\n3\n");
+
+    for phase in ["Scanning:", "Parsing:", "Resolving:", "Interpreting:"] {
+        assert!(stderr.contains(phase), "missing '{}' in stderr", phase);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_run_strict_unused_variable_err() -> Result<()> {
+    // -- Setup & Fixtures
+    let path = write_program("strict", "{\n  var unused = 1;\n}\nprint 1;\n")?;
+
+    // -- Exec
+    let lenient = execute("run", &path)?;
+    let strict = execute_with("run", &path, &["--strict"])?;
+    fs::remove_file(&path)?;
+
+    // -- Check
+    assert!(lenient.status.success());
+    assert_eq!(String::from_utf8(lenient.stdout)?, "1\n");
+    assert!(String::from_utf8(lenient.stderr)?.contains("Warning: Unused local variable"));
+
+    assert_eq!(strict.status.code(), Some(65));
+    assert_eq!(String::from_utf8(strict.stdout)?, "");
+    assert!(String::from_utf8(strict.stderr)?.contains("Error: Unused local variable"));
+
+    Ok(())
+}
+
+#[test]
+fn test_run_call_returned_function_ok() -> Result<()> {
+    // -- Setup & Fixtures
+    let path = write_program(
+        "curry",
+        "fun makeAdder(a) {\n  fun add(b) {\n    return a + b;\n  }\n  return add;\n}\nprint makeAdder(3)(4);\n",
+    )?;
+
+    // -- Exec
+    let output = execute("run", &path)?;
+    fs::remove_file(&path)?;
+
+    // -- Check
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?, "7\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_evaluate_literal_ok() -> Result<()> {
+    // -- Setup & Fixtures
+    let fx_cases = [
+        ("literal-string", "\"hello\"", "hello\n"),
+        ("literal-number", "42", "42\n"),
+    ];
+
+    for (name, source, expected) in fx_cases {
+        let path = write_program(name, source)?;
+
+        // -- Exec
+        let output = execute("evaluate", &path)?;
+        fs::remove_file(&path)?;
+
+        // -- Check
+        assert!(output.status.success(), "`{}` failed", source);
+        assert_eq!(String::from_utf8(output.stdout)?, expected);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_evaluate_trailing_tokens_err() -> Result<()> {
+    // -- Setup & Fixtures
+    let path = write_program("trailing", "1 + 2 3")?;
+
+    // -- Exec
+    let output = execute("evaluate", &path)?;
+    fs::remove_file(&path)?;
+
+    // -- Check
+    assert_eq!(output.status.code(), Some(65));
+    assert_eq!(String::from_utf8(output.stdout)?, "");
+    assert!(String::from_utf8(output.stderr)?.contains("Unexpected trailing tokens."));
+
+    Ok(())
+}
+
+#[test]
+fn test_run_time_limit_err() -> Result<()> {
+    // -- Setup & Fixtures
+    let path = write_program("time-limit", "print 1;\nwhile (true) {}\n")?;
+
+    // -- Exec
+    let output = execute_with("run", &path, &["--time-limit=0.05"])?;
+    fs::remove_file(&path)?;
+
+    // -- Check
+    assert_eq!(output.status.code(), Some(70));
+    assert_eq!(String::from_utf8(output.stdout)?, "1\n");
+    assert!(String::from_utf8(output.stderr)?.contains("Time limit exceeded."));
+
+    Ok(())
+}
+
+#[test]
+fn test_dump_program_ok() -> Result<()> {
+    // -- Setup & Fixtures
+    let path = write_program(
+        "dump",
+        "var a = 1;\nprint a + 2;\n{\n  a = 3;\n}\nfun id(x) { return x; }\n",
+    )?;
+
+    // -- Exec
+    let output = execute("dump", &path)?;
+    fs::remove_file(&path)?;
+
+    // -- Check
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        "var a = 1.0\nprint (+ a 2.0)\n{\na = 3.0\n}\nfn id(x) {return x}\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_run_no_resolve_ok() -> Result<()> {
+    // -- Setup & Fixtures
+    let path = write_program(
+        "no-resolve",
+        "var a = \"global\";\n{\n  var a = \"local\";\n  print a;\n}\n",
+    )?;
+
+    // -- Exec
+    let resolved = execute("run", &path)?;
+    let unresolved = execute_with("run", &path, &["--no-resolve"])?;
+    fs::remove_file(&path)?;
+
+    // -- Check
+    assert!(resolved.status.success());
+    assert_eq!(String::from_utf8(resolved.stdout)?, "local\n");
+
+    // Unresolved reads go to globals
+    assert!(unresolved.status.success());
+    assert_eq!(String::from_utf8(unresolved.stdout)?, "global\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_run_native_error_err() -> Result<()> {
+    // -- Setup & Fixtures
+    let path = write_program("native-error", "print 1;\nprint parse_number(\"abc\");\n")?;
+
+    // -- Exec
+    let output = execute("run", &path)?;
+    fs::remove_file(&path)?;
+
+    // -- Check
+    assert_eq!(output.status.code(), Some(70));
+    assert_eq!(String::from_utf8(output.stdout)?, "1\n");
+    assert_eq!(
+        String::from_utf8(output.stderr)?,
+        "[line 2] Error: Can't parse 'abc' as a number.\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_run_native_type_error_line_err() -> Result<()> {
+    // -- Setup & Fixtures
+    let fx_cases = [
+        (
+            "print 1;\nprint abs(\"x\");\n",
+            "[line 2] Error: Arguments must be numbers.\n",
+        ),
+        (
+            "print 1;\n\nkeys(1);\n",
+            "[line 3] Error: Argument must be a map.\n",
+        ),
+        (
+            "print 1;\nchars(nil);\n",
+            "[line 2] Error: Argument must be a string.\n",
+        ),
+    ];
+
+    for (source, expected) in fx_cases {
+        let path = write_program("native-type-error", source)?;
+
+        // -- Exec
+        let output = execute("run", &path)?;
+        fs::remove_file(&path)?;
+
+        // -- Check
+        assert_eq!(output.status.code(), Some(70), "{}", source);
+        assert_eq!(String::from_utf8(output.stderr)?, expected, "{}", source);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_scan_error_source_snippet_err() -> Result<()> {
+    // -- Setup & Fixtures
+    let path = write_program("snippet", "print 1;\nprint 2 $ 3;\n")?;
+
+    // -- Exec
+    let plain = execute("tokenize", &path)?;
+    let output = Command::new(env!("CARGO_BIN_EXE_interpreter"))
+        .arg("tokenize")
+        .arg(&path)
+        .env("RUST_LOG", "off")
+        .env("LOX_SHOW_SOURCE", "true")
+        .output()?;
+    fs::remove_file(&path)?;
+
+    // -- Check
+    assert_eq!(
+        String::from_utf8(plain.stderr)?,
+        "[line 2] Error: Unexpected character: $\n"
+    );
+
+    assert_eq!(output.status.code(), Some(65));
+    assert_eq!(
+        String::from_utf8(output.stderr)?,
+        "[line 2] Error: Unexpected character: $\n2 | print 2 $ 3;\n  |         ^\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_usage_lists_commands_err() -> Result<()> {
+    // -- Setup & Fixtures
+    let path = write_program("usage", "print 1;")?;
+    let binary = || {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_interpreter"));
+        command.env("RUST_LOG", "off");
+        command
+    };
+
+    // -- Exec
+    let missing = binary().output()?;
+    let unknown = execute("compile", &path)?;
+    let no_file = binary().arg("run").output()?;
+    let help = binary().arg("--help").output()?;
+    fs::remove_file(&path)?;
+
+    // -- Check
+    for (output, message) in [
+        (&missing, "Missing command."),
+        (&unknown, "Unknown command 'compile'."),
+        (&no_file, "Missing filename."),
+    ] {
+        let stderr = String::from_utf8(output.stderr.clone())?;
+
+        assert_eq!(output.status.code(), Some(64));
+        assert!(stderr.starts_with(message), "{}", stderr);
+        for command in ["tokenize", "parse", "evaluate", "run"] {
+            assert!(
+                stderr.contains(&format!("  {} ", command)),
+                "missing {}",
+                command
+            );
+        }
+    }
+
+    assert!(help.status.success());
+    assert!(String::from_utf8(help.stdout)?.contains("Commands:"));
+
+    Ok(())
+}
+
+#[test]
+fn test_run_invalid_utf8_file_err() -> Result<()> {
+    // -- Setup & Fixtures
+    let path = std::env::temp_dir().join(format!("lox-cli-{}-binary.lox", std::process::id()));
+    fs::write(&path, [b'v', b'a', b'r', 0xff, 0xfe])?;
+
+    // -- Exec
+    let output = execute("run", &path)?;
+    fs::remove_file(&path)?;
+
+    // -- Check
+    let stderr = String::from_utf8(output.stderr)?;
+
+    assert_eq!(output.status.code(), Some(66));
+    assert_eq!(
+        stderr,
+        format!("File '{}' is not valid UTF-8.\n", path.display())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_run_invalid_time_limit_err() -> Result<()> {
+    // -- Setup & Fixtures
+    let path = write_program("time-limit", "print 1;\n")?;
+
+    // -- Exec
+    let output = execute_with("run", &path, &["--time-limit=abc"])?;
+    fs::remove_file(&path)?;
+
+    // -- Check
+    let stderr = String::from_utf8(output.stderr)?;
+
+    assert_eq!(output.status.code(), Some(64));
+    assert_eq!(String::from_utf8(output.stdout)?, "");
+    assert!(
+        stderr.starts_with("Invalid time limit 'abc'.\n\nUsage:"),
+        "{}",
+        stderr
+    );
+
+    Ok(())
+}